@@ -0,0 +1,426 @@
+use crate::models::{Basis, Detection, HackerConfig, NoiseModel, AttackReport};
+use crate::photons::Photon;
+use crate::polarization::Polarization;
+use crate::protocol::register_hits;
+use crate::rng::PhotonRng;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_1_SQRT_2;
+
+/// A pulse on its way to Bob: `photons` copies of one polarization state.
+#[derive(Debug, Clone, Copy)]
+pub struct InFlight {
+    pub polarization: Polarization,
+    pub photons: u32,
+}
+
+/// What Eve learned about one pulse.
+#[derive(Debug, Clone, Copy)]
+pub struct EveReading {
+    pub basis: Basis, // the basis her value refers to
+    pub value: u8,
+    pub state: Polarization, // the state she found
+}
+
+/// Outcome of an attack on one pulse.
+pub struct Interception {
+    pub forwarded: Option<InFlight>, // None when Eve blocks the pulse
+    pub reading: Option<EveReading>,
+    pub lossless: bool, // forwarded over Eve's own line, skipping channel loss
+}
+
+impl Interception {
+    pub fn untouched(photon: InFlight) -> Self {
+        Self {
+            forwarded: Some(photon),
+            reading: None,
+            lossless: false,
+        }
+    }
+}
+
+/// What sifting makes public about a pulse, as far as it helps Eve read a
+/// photon she stored: the two states Alice's is then known to be one of,
+/// each as the reading Eve reports when she settles on it. A basis
+/// announcement names the basis' two states, SARG04 its announced pair and
+/// E91 Alice's analyzer axis and the one orthogonal to it.
+#[derive(Debug, Clone, Copy)]
+pub struct Announcement {
+    pub candidates: [EveReading; 2],
+}
+
+impl Announcement {
+    pub fn of(basis: Basis) -> Self {
+        Self::pair((basis, 0), (basis, 1))
+    }
+
+    // Alice's state is one of two `(basis, value)` states
+    pub fn pair(first: (Basis, u8), second: (Basis, u8)) -> Self {
+        let candidate = |(basis, value)| EveReading { basis, value, state: Polarization::of(basis, value) };
+        Self { candidates: [candidate(first), candidate(second)] }
+    }
+
+    // Alice's analyzer axis at `degrees`; results are reported as
+    // rectilinear values relative to it
+    pub fn analyzer(degrees: f64) -> Self {
+        let candidate = |value: u8| EveReading {
+            basis: Basis::Rectilinear,
+            value,
+            state: Polarization::linear(degrees + 90.0 * value as f64),
+        };
+        Self { candidates: [candidate(0), candidate(1)] }
+    }
+
+    // Eve measures a stored photon along the axis that best tells the two
+    // candidates apart and settles on the one her result points to
+    fn measure(self, photon: Polarization, rng: &mut PhotonRng) -> EveReading {
+        let [first, second] = self.candidates.map(|candidate| candidate.state);
+        self.candidates[photon.measure_along(first.axis_against(second), rng) as usize]
+    }
+}
+
+/// An eavesdropping strategy acting on pulses in flight.
+///
+/// `bases` are the bases the protocol prepares states in. `announced` is
+/// revealed during sifting, `None` when the protocol reveals nothing that
+/// narrows Alice's state down: attacks that store photons may use it to
+/// measure, attacks that measure at once must not.
+pub trait Attack: Send + Sync {
+    fn intercept(&self, photon: InFlight, announced: Option<Announcement>, bases: &[Basis], rng: &mut PhotonRng) -> Interception;
+
+    // Whether Eve sits right after Alice, ahead of the lossy channel
+    fn at_source(&self) -> bool {
+        false
+    }
+
+    // QBER the attack should cause on a matching-basis position, if known
+    fn expected_qber(&self, _bases: &[Basis]) -> Option<f64> {
+        None
+    }
+}
+
+// Eve measures every photon in a random basis and resends what she saw
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterceptResend {
+    pub measurement_error_rate: f64, // 0.0 to 1.0
+    pub resend_error_rate: f64,      // 0.0 to 1.0
+}
+
+// Intercept-resend on a random fraction of the photons only
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialIntercept {
+    pub interception_rate: f64,      // 0.0 to 1.0
+    pub measurement_error_rate: f64, // 0.0 to 1.0
+    pub resend_error_rate: f64,      // 0.0 to 1.0
+}
+
+// Eve measures in the intermediate basis at 22.5°/112.5°, which guesses
+// either BB84 basis' bit right cos²(22.5°) ≈ 85% of the time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Breidbart {
+    pub interception_rate: f64,
+}
+
+// A beam splitter diverts each photon to Eve with probability `split_ratio`;
+// she stores them and measures once sifting is over, see `Announcement`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeamSplitting {
+    pub split_ratio: f64,
+}
+
+// Optimal phase-covariant 1→2 cloner for BB84 states: Bob and Eve each get a
+// copy with fidelity (1 + 1/√2)/2 ≈ 85.4%; Eve measures hers once sifting
+// is over
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseCovariantCloning {
+    pub interception_rate: f64,
+}
+
+// Eve blocks single-photon pulses, keeps one photon of every larger pulse
+// and forwards the rest over a lossless line. On single-photon simulators she
+// can only block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhotonNumberSplitting {
+    pub interception_rate: f64,
+}
+
+// Eve replaces the entangled-pair source; prepare-and-measure channels are
+// left alone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceControl {
+    pub interception_rate: f64,
+}
+
+impl HackerConfig {
+    pub fn attack(&self) -> &dyn Attack {
+        match self {
+            HackerConfig::InterceptResend(attack) => attack,
+            HackerConfig::PartialIntercept(attack) => attack,
+            HackerConfig::Breidbart(attack) => attack,
+            HackerConfig::BeamSplitting(attack) => attack,
+            HackerConfig::PhaseCovariantCloning(attack) => attack,
+            HackerConfig::PhotonNumberSplitting(attack) => attack,
+            HackerConfig::SourceControl(attack) => attack,
+        }
+    }
+}
+
+impl HackerConfig {
+    /// The same attack at another strength: the interception rate, or the
+    /// split ratio for beam splitting. Intercept-resend becomes partial.
+    pub fn with_interception_rate(self, rate: f64) -> HackerConfig {
+        match self {
+            HackerConfig::InterceptResend(attack) => HackerConfig::PartialIntercept(PartialIntercept {
+                interception_rate: rate,
+                measurement_error_rate: attack.measurement_error_rate,
+                resend_error_rate: attack.resend_error_rate,
+            }),
+            HackerConfig::PartialIntercept(attack) => {
+                HackerConfig::PartialIntercept(PartialIntercept { interception_rate: rate, ..attack })
+            }
+            HackerConfig::Breidbart(_) => HackerConfig::Breidbart(Breidbart { interception_rate: rate }),
+            HackerConfig::BeamSplitting(_) => HackerConfig::BeamSplitting(BeamSplitting { split_ratio: rate }),
+            HackerConfig::PhaseCovariantCloning(_) => {
+                HackerConfig::PhaseCovariantCloning(PhaseCovariantCloning { interception_rate: rate })
+            }
+            HackerConfig::PhotonNumberSplitting(_) => {
+                HackerConfig::PhotonNumberSplitting(PhotonNumberSplitting { interception_rate: rate })
+            }
+            HackerConfig::SourceControl(_) => HackerConfig::SourceControl(SourceControl { interception_rate: rate }),
+        }
+    }
+}
+
+impl Attack for InterceptResend {
+    fn intercept(&self, photon: InFlight, _announced: Option<Announcement>, bases: &[Basis], rng: &mut PhotonRng) -> Interception {
+        intercept_resend(photon, bases, self.measurement_error_rate, self.resend_error_rate, rng)
+    }
+
+    fn expected_qber(&self, bases: &[Basis]) -> Option<f64> {
+        Some(wrong_basis_qber(bases))
+    }
+}
+
+impl Attack for PartialIntercept {
+    fn intercept(&self, photon: InFlight, _announced: Option<Announcement>, bases: &[Basis], rng: &mut PhotonRng) -> Interception {
+        if !rng.chance(self.interception_rate) {
+            return Interception::untouched(photon);
+        }
+        intercept_resend(photon, bases, self.measurement_error_rate, self.resend_error_rate, rng)
+    }
+
+    fn expected_qber(&self, bases: &[Basis]) -> Option<f64> {
+        Some(self.interception_rate * wrong_basis_qber(bases))
+    }
+}
+
+impl Attack for Breidbart {
+    fn intercept(&self, photon: InFlight, _announced: Option<Announcement>, _bases: &[Basis], rng: &mut PhotonRng) -> Interception {
+        if !rng.chance(self.interception_rate) {
+            return Interception::untouched(photon);
+        }
+        // 0° and 45° both sit 22.5° from the analyzer, so result 0 means bit 0
+        // in either basis; the reading is reported against the rectilinear one
+        let value = photon.polarization.measure_along(Polarization::linear(BREIDBART_ANGLE), rng);
+        let state = Polarization::linear(BREIDBART_ANGLE + 90.0 * value as f64);
+        Interception {
+            forwarded: Some(InFlight { polarization: state, photons: 1 }),
+            reading: Some(EveReading { basis: Basis::Rectilinear, value, state }),
+            lossless: false,
+        }
+    }
+
+    // Resending her guess from between the bases costs as much as a random basis
+    fn expected_qber(&self, bases: &[Basis]) -> Option<f64> {
+        Some(self.interception_rate * wrong_basis_qber(bases))
+    }
+}
+
+impl Attack for BeamSplitting {
+    fn intercept(&self, photon: InFlight, announced: Option<Announcement>, bases: &[Basis], rng: &mut PhotonRng) -> Interception {
+        let diverted = (0..photon.photons).filter(|_| rng.chance(self.split_ratio)).count() as u32;
+        let reading = (diverted > 0).then(|| read_stored(photon.polarization, announced, bases, rng));
+        Interception {
+            forwarded: (photon.photons > diverted).then_some(InFlight { photons: photon.photons - diverted, ..photon }),
+            reading,
+            lossless: false,
+        }
+    }
+
+    fn expected_qber(&self, _bases: &[Basis]) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+impl Attack for PhaseCovariantCloning {
+    fn intercept(&self, photon: InFlight, announced: Option<Announcement>, bases: &[Basis], rng: &mut PhotonRng) -> Interception {
+        if !rng.chance(self.interception_rate) {
+            return Interception::untouched(photon);
+        }
+        let copy = photon.polarization.scaled(FRAC_1_SQRT_2);
+        Interception {
+            forwarded: Some(InFlight { polarization: copy, ..photon }),
+            reading: Some(read_stored(copy, announced, bases, rng)),
+            lossless: false,
+        }
+    }
+
+    fn expected_qber(&self, _bases: &[Basis]) -> Option<f64> {
+        Some(self.interception_rate * (1.0 - FRAC_1_SQRT_2) / 2.0)
+    }
+}
+
+impl Attack for PhotonNumberSplitting {
+    fn intercept(&self, photon: InFlight, announced: Option<Announcement>, bases: &[Basis], rng: &mut PhotonRng) -> Interception {
+        if photon.photons == 0 || !rng.chance(self.interception_rate) {
+            return Interception::untouched(photon);
+        }
+        let forwarded = photon.photons - 1;
+        let reading = (forwarded > 0).then(|| read_stored(photon.polarization, announced, bases, rng));
+        Interception {
+            forwarded: (forwarded > 0).then_some(InFlight { photons: forwarded, ..photon }),
+            reading,
+            lossless: true,
+        }
+    }
+
+    fn at_source(&self) -> bool {
+        true
+    }
+
+    fn expected_qber(&self, _bases: &[Basis]) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+impl Attack for SourceControl {
+    fn intercept(&self, photon: InFlight, _announced: Option<Announcement>, _bases: &[Basis], _rng: &mut PhotonRng) -> Interception {
+        Interception::untouched(photon)
+    }
+}
+
+// Analyzer angle of the Breidbart basis
+pub const BREIDBART_ANGLE: f64 = 22.5;
+
+// Eve picks one of k bases and is wrong (k − 1)/k of the time, leaving Bob a
+// coin flip: 25% for BB84, 33% for the six-state protocol
+fn wrong_basis_qber(bases: &[Basis]) -> f64 {
+    (1.0 - 1.0 / bases.len() as f64) / 2.0
+}
+
+// Eve reads a photon she stored once sifting is over: against the announced
+// candidates, or in a random basis when nothing was announced
+fn read_stored(photon: Polarization, announced: Option<Announcement>, bases: &[Basis], rng: &mut PhotonRng) -> EveReading {
+    match announced {
+        Some(announced) => announced.measure(photon, rng),
+        None => {
+            let basis = rng.basis(bases);
+            let value = photon.measure(basis, rng);
+            EveReading { basis, value, state: Polarization::of(basis, value) }
+        }
+    }
+}
+
+fn intercept_resend(
+    photon: InFlight,
+    bases: &[Basis],
+    measurement_error_rate: f64,
+    resend_error_rate: f64,
+    rng: &mut PhotonRng,
+) -> Interception {
+    // Hacker's random basis choice
+    let basis = rng.basis(bases);
+    let reading = photon.polarization.measure(basis, rng);
+    let value = if rng.chance(measurement_error_rate) { 1 - reading } else { reading };
+
+    // Hacker resends new photon to Bob (with possible error)
+    let resend_value = if rng.chance(resend_error_rate) { rng.bit() } else { value };
+
+    Interception {
+        forwarded: Some(InFlight { polarization: Polarization::of(basis, resend_value), photons: 1 }),
+        reading: Some(EveReading { basis, value, state: Polarization::of(basis, value) }),
+        lossless: false,
+    }
+}
+
+/// Carry `photon` from Alice towards Bob through channel loss and, if Eve is
+/// present, her attack. Returns what reaches Bob and Eve's record.
+pub fn transmit(
+    photon: InFlight,
+    announced: Option<Announcement>,
+    attack: Option<&dyn Attack>,
+    loss_probability: f64,
+    bases: &[Basis],
+    rng: &mut PhotonRng,
+) -> (Option<InFlight>, Option<Photon>) {
+    let (mut photon, mut reading, lossless) = match attack {
+        Some(attack) if attack.at_source() => {
+            let interception = attack.intercept(photon, announced, bases, rng);
+            (interception.forwarded, interception.reading, interception.lossless)
+        }
+        _ => (Some(photon), None, false),
+    };
+
+    // Apply photon loss model
+    if !lossless {
+        photon = photon.and_then(|photon| {
+            let survivors = (0..photon.photons).filter(|_| !rng.chance(loss_probability)).count() as u32;
+            (survivors > 0).then_some(InFlight { photons: survivors, ..photon })
+        });
+    }
+
+    if let (Some(attack), Some(arriving)) = (attack.filter(|attack| !attack.at_source()), photon) {
+        let interception = attack.intercept(arriving, announced, bases, rng);
+        photon = interception.forwarded;
+        reading = interception.reading;
+    }
+
+    let intercepted = reading.map(|reading| Photon {
+        polarization: reading.state.degrees(),
+        ..Photon::prepared(reading.basis, reading.value)
+    });
+    (photon, intercepted)
+}
+
+/// Compare Eve's records with the sifted key. `decisions` holds the sifted
+/// (Alice, Bob) bits by position, as passed to `ProtocolCore::reconcile`,
+/// and `eve_view` her guess where she has a record (see `eve_view`).
+pub fn attack_report(decisions: &[Option<(u8, u8)>], eve_view: &[Option<u8>]) -> AttackReport {
+    let (mut attacked_bits, mut correct_guesses, mut errors) = (0, 0, 0);
+    for (decision, eve) in decisions.iter().zip(eve_view) {
+        let (Some((alice, bob)), Some(guess)) = (*decision, *eve) else {
+            continue;
+        };
+        attacked_bits += 1;
+        if guess == alice {
+            correct_guesses += 1;
+        }
+        if alice != bob {
+            errors += 1;
+        }
+    }
+
+    AttackReport {
+        attacked_bits,
+        correct_guesses,
+        guess_accuracy: if attacked_bits == 0 { 0.0 } else { correct_guesses as f64 / attacked_bits as f64 },
+        induced_qber: if attacked_bits == 0 { 0.0 } else { errors as f64 / attacked_bits as f64 },
+    }
+}
+
+/// Bob measures whatever reached him along `axis` (his basis' 0 state).
+/// Every photon is detected on its own, so a multi-photon pulse can fire
+/// both detectors; either may also fire on a dark count.
+pub fn detect(arriving: Option<InFlight>, axis: Polarization, noise_model: &NoiseModel, rng: &mut PhotonRng) -> (Detection, u8) {
+    let mut hits = [false; 2];
+    if let Some(photon) = arriving {
+        for _ in 0..photon.photons {
+            if rng.chance(noise_model.detector_efficiency) {
+                let reading = photon.polarization.measure_along(axis, rng);
+                // Apply measurement error (1%)
+                let reading = if rng.chance(0.01) { 1 - reading } else { reading };
+                hits[reading as usize] = true;
+            }
+        }
+    }
+    register_hits(hits, noise_model, rng)
+}
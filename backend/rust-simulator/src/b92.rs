@@ -0,0 +1,103 @@
+use crate::bits::BitVec;
+use crate::models::{Basis, KeyRateBound};
+use crate::photons::{Photon, PhotonColumns};
+use crate::polarization::Polarization;
+use crate::protocol::{QkdProtocol, ProtocolCore, PARALLEL_THRESHOLD};
+use rayon::prelude::*;
+
+// B92 uses only two non-orthogonal states:
+//   0 -> |0⟩ (0°, rectilinear)
+//   1 -> |+⟩ (45°, diagonal)
+// Alice's photons therefore always carry value 0 within their basis and the
+// key bit is the basis. Bob measures in a random basis; the orthogonal result
+// (90° or 135°) rules out one of the two states and is the only conclusive one.
+pub struct B92Simulator {
+    core: ProtocolCore,
+}
+
+impl B92Simulator {
+    pub fn new() -> Self {
+        Self {
+            core: ProtocolCore::new("B92"),
+        }
+    }
+}
+
+impl Default for B92Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QkdProtocol for B92Simulator {
+    fn name(&self) -> &'static str {
+        "b92"
+    }
+
+    fn core(&self) -> &ProtocolCore {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut ProtocolCore {
+        &mut self.core
+    }
+
+    fn generate_alice_bits(&mut self, count: usize) -> &PhotonColumns {
+        self.core.prepare_states(count, |rng| {
+            let basis = if rng.bit() == 0 { Basis::Rectilinear } else { Basis::Diagonal };
+            (basis, 0)
+        })
+    }
+
+    // B92's security depends on the states' overlap and has no QBER-only bound
+    fn key_rate_bound(&self) -> Option<KeyRateBound> {
+        None
+    }
+
+    // Only Bob's conclusive positions are announced, which says nothing about
+    // Alice's state, so Eve has nothing to read a stored photon against
+    fn measure_bits(&mut self, hacker_present: bool) -> &PhotonColumns {
+        self.core.measure_announced(hacker_present, |_, _| None)
+    }
+
+    // Bob announces which positions gave a conclusive result (~25% of them)
+    fn sift_key(&mut self) -> &BitVec {
+        let alice_bits = &self.core.state.alice_bits;
+        let bob_bits = &self.core.state.bob_bits;
+        let sift = |i: usize| conclusive_bit(&bob_bits.get(i)).map(|bob| (basis_bit(alice_bits.basis(i)), bob));
+
+        // Use parallel processing for large counts
+        let count = self.core.sifting_positions();
+        let decisions: Vec<Option<(u8, u8)>> = if count > PARALLEL_THRESHOLD {
+            (0..count).into_par_iter().map(sift).collect()
+        } else {
+            (0..count).map(sift).collect()
+        };
+
+        self.core.assess_eavesdropper_with(&decisions, |_, reading| eve_key_bit(reading));
+        self.core.reconcile(decisions)
+    }
+}
+
+// The key bit Bob infers from a conclusive result, if he got one. A click at
+// 90° excludes |0⟩ (so Alice sent 1); a click at 135° excludes |+⟩ (so 0).
+fn conclusive_bit(bob_bit: &Photon) -> Option<u8> {
+    (bob_bit.is_detected() && bob_bit.value == 1).then(|| 1 - basis_bit(bob_bit.basis))
+}
+
+// Eve's guess of the key bit: the B92 state nearer the one she found, 0°
+// for 0 and 45° for 1. A state as near to both tells her nothing; she then
+// guesses 0.
+fn eve_key_bit(reading: &Photon) -> u8 {
+    let found = Polarization::from_degrees(reading.polarization);
+    let states = [Polarization::linear(0.0), Polarization::linear(45.0)];
+    found.nearest(states).map_or(0, |state| state as u8)
+}
+
+fn basis_bit(basis: Basis) -> u8 {
+    match basis {
+        Basis::Rectilinear => 0,
+        Basis::Diagonal => 1,
+        Basis::Circular => unreachable!("B92 only uses rectilinear and diagonal bases"),
+    }
+}
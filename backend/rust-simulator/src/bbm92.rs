@@ -0,0 +1,156 @@
+use crate::attack::Announcement;
+use crate::bits::BitVec;
+use crate::models::{Basis, HackerConfig, NoiseModel, SourcePosition};
+use crate::photons::{Photon, PhotonColumns, Party};
+use crate::protocol::{QkdProtocol, ProtocolCore, PARALLEL_THRESHOLD, UNPACKED_CHUNK, measure_photon, register_clicks};
+use crate::rng::{PhotonRng, Stream};
+use rayon::prelude::*;
+
+// BBM92 is BB84 run on |Φ+⟩ pairs: Alice and Bob each measure their photon
+// in a random BB84 basis, and since the pair is correlated in both bases the
+// matching-basis positions form the key through the usual sifting. Alice's
+// bits hold her basis from `generate` and her result after `measure`.
+//
+// `loss_probability` is the loss of the whole Alice–Bob link; the source
+// position decides how it splits between the two arms. A `source_control`
+// hacker replaces the source: for `interception_rate` of the pairs she sends
+// both parties the same BB84 state instead, which shows up as a 25% QBER.
+// Any other attack acts on Bob's arm like on a prepare-and-measure channel.
+pub struct BBM92Simulator {
+    core: ProtocolCore,
+}
+
+impl BBM92Simulator {
+    pub fn new() -> Self {
+        Self {
+            core: ProtocolCore::new("BBM92"),
+        }
+    }
+}
+
+impl Default for BBM92Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QkdProtocol for BBM92Simulator {
+    fn name(&self) -> &'static str {
+        "bbm92"
+    }
+
+    fn core(&self) -> &ProtocolCore {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut ProtocolCore {
+        &mut self.core
+    }
+
+    // Alice only picks her measurement basis up front
+    fn generate_alice_bits(&mut self, count: usize) -> &PhotonColumns {
+        let bases = self.core.bases;
+        self.core.prepare_states(count, |rng| (rng.basis(bases), 0))
+    }
+
+    fn measure_bits(&mut self, hacker_present: bool) -> &PhotonColumns {
+        let hacker_config = self.core.hacker_config.clone();
+        let noise_model = self.core.noise_model.clone();
+        let bases = self.core.bases;
+        let seed = self.core.state.seed;
+        let (alice_arm, bob_arm) = arm_losses(self.core.source_position, noise_model.loss_probability);
+        let alice_bits = &self.core.state.alice_bits;
+        let emit = |index: usize| {
+            let mut rng = PhotonRng::new(seed, Stream::Source, index);
+            emit_pair(&alice_bits.get(index), alice_arm, hacker_present, &hacker_config, &noise_model, &mut rng)
+        };
+
+        // Alice's results replace her basis choices
+        // Use parallel processing for large counts
+        let count = alice_bits.len();
+        let epoch = self.core.state.start_time;
+        let mut measured = PhotonColumns::dense(Party::Alice, epoch);
+        let mut bob_photons = PhotonColumns::dense(Party::Bob, epoch);
+        let mut source_controlled = BitVec::with_capacity(count);
+        for start in (0..count).step_by(UNPACKED_CHUNK) {
+            let chunk = start..(start + UNPACKED_CHUNK).min(count);
+            let pairs: Vec<(Photon, Photon, bool)> = if count > PARALLEL_THRESHOLD {
+                chunk.into_par_iter().map(emit).collect()
+            } else {
+                chunk.map(emit).collect()
+            };
+            for (alice_bit, bob_photon, controlled) in pairs {
+                measured.push(alice_bit);
+                bob_photons.push(bob_photon);
+                source_controlled.push(u8::from(controlled));
+            }
+        }
+        self.core.state.alice_bits = measured;
+
+        // Bob's arm carries whatever the source sent him
+        let bob_noise = NoiseModel {
+            loss_probability: bob_arm,
+            ..noise_model.clone()
+        };
+        self.core.measure_with(hacker_present, |index, _, rng| {
+            let bob_photon = bob_photons.get(index);
+            let announced = Some(Announcement::of(bob_photon.basis));
+            let (bob_bit, intercepted) =
+                measure_photon(&bob_photon, announced, hacker_present, &hacker_config, &bob_noise, bases, rng);
+            // Where Eve made the pair her record is the state she sent Bob
+            let source_reading = (source_controlled.get(index) == 1).then_some(bob_photon);
+            (bob_bit, source_reading.or(intercepted))
+        })
+    }
+}
+
+// Loss on Alice's and Bob's arm such that the link as a whole loses
+// `loss_probability` of the pairs' photons
+fn arm_losses(position: SourcePosition, loss_probability: f64) -> (f64, f64) {
+    let transmission = 1.0 - loss_probability;
+    match position {
+        SourcePosition::Alice => (0.0, loss_probability),
+        SourcePosition::Midway => (1.0 - transmission.sqrt(), 1.0 - transmission.sqrt()),
+        SourcePosition::Bob => (loss_probability, 0.0),
+    }
+}
+
+// Emit one pair and let Alice measure her photon. Returns Alice's measured
+// bit, the state Bob's photon is left in and whether Eve made the pair.
+fn emit_pair(
+    alice_bit: &Photon,
+    alice_arm: f64,
+    hacker_present: bool,
+    hacker_config: &HackerConfig,
+    noise_model: &NoiseModel,
+    rng: &mut PhotonRng,
+) -> (Photon, Photon, bool) {
+    let alice_basis = alice_bit.basis;
+
+    // Eve's source sends a known BB84 state to both sides; Alice's result is
+    // random unless she happens to use Eve's basis
+    let source_control = match hacker_config {
+        HackerConfig::SourceControl(source) if hacker_present => source.interception_rate,
+        _ => 0.0,
+    };
+    let (alice_reading, bob_photon, controlled) =
+        if rng.chance(source_control) {
+            let hacker_basis = rng.basis(&Basis::BB84);
+            let hacker_value = rng.bit();
+            let alice_reading = if alice_basis == hacker_basis { hacker_value } else { rng.bit() };
+            (alice_reading, (hacker_basis, hacker_value), true)
+        } else {
+            // Alice's result is a coin flip and projects Bob's photon onto it
+            let alice_reading = rng.bit();
+            (alice_reading, (alice_basis, alice_reading), false)
+        };
+
+    let signal = (!rng.chance(alice_arm) && rng.chance(noise_model.detector_efficiency)).then_some(alice_reading);
+    let (detection, alice_value) = register_clicks(signal, noise_model, rng);
+
+    let measured = Photon {
+        detection: Some(detection),
+        ..Photon::prepared(alice_basis, alice_value)
+    };
+    (measured, Photon::prepared(bob_photon.0, bob_photon.1), controlled)
+}
@@ -0,0 +1,122 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+/// Bits packed 64 to a word, least significant bit first.
+///
+/// Keys are kept in this form; they serialize as strings of '0' and '1'.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(bits: usize) -> Self {
+        Self {
+            words: Vec::with_capacity(bits.div_ceil(64)),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+        self.len = 0;
+    }
+
+    pub fn push(&mut self, bit: u8) {
+        if self.len.is_multiple_of(64) {
+            self.words.push(0);
+        }
+        self.words[self.len / 64] |= ((bit & 1) as u64) << (self.len % 64);
+        self.len += 1;
+    }
+
+    pub fn get(&self, index: usize) -> u8 {
+        assert!(index < self.len, "bit {} out of range for length {}", index, self.len);
+        ((self.words[index / 64] >> (index % 64)) & 1) as u8
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len).map(|index| self.get(index))
+    }
+
+    // The packed words; bits past `len` are zero
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    // One byte per bit, for algorithms that flip bits at random positions
+    pub fn unpack(&self) -> Vec<u8> {
+        self.iter().collect()
+    }
+}
+
+impl FromIterator<u8> for BitVec {
+    fn from_iter<I: IntoIterator<Item = u8>>(bits: I) -> Self {
+        let bits = bits.into_iter();
+        let mut packed = BitVec::with_capacity(bits.size_hint().0);
+        for bit in bits {
+            packed.push(bit);
+        }
+        packed
+    }
+}
+
+impl Extend<u8> for BitVec {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, bits: I) {
+        for bit in bits {
+            self.push(bit);
+        }
+    }
+}
+
+impl fmt::Display for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = self.iter().map(|bit| char::from(b'0' + bit)).collect();
+        f.write_str(&text)
+    }
+}
+
+impl Serialize for BitVec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for BitVec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(BitStringVisitor)
+    }
+}
+
+struct BitStringVisitor;
+
+impl Visitor<'_> for BitStringVisitor {
+    type Value = BitVec;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string of '0' and '1'")
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<BitVec, E> {
+        text.bytes()
+            .map(|c| match c {
+                b'0' | b'1' => Ok(c - b'0'),
+                _ => Err(E::invalid_value(de::Unexpected::Str(text), &self)),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,127 @@
+use crate::models::ErrorCorrectionReport;
+use crate::rng::PhotonRng;
+use crate::stats::binary_entropy;
+use rand::seq::SliceRandom;
+
+pub const CASCADE_PASSES: usize = 4;
+
+/// Bob's key after Cascade together with what it cost.
+pub struct CascadeOutcome {
+    pub corrected: Vec<u8>,
+    pub report: ErrorCorrectionReport,
+}
+
+// One Cascade pass: a shuffle of the key split into blocks of `block_size`
+struct Pass {
+    order: Vec<usize>,    // pass position -> key index
+    position: Vec<usize>, // key index -> pass position
+    block_size: usize,
+}
+
+impl Pass {
+    fn block_range(&self, block: usize) -> (usize, usize) {
+        let start = block * self.block_size;
+        (start, (start + self.block_size).min(self.order.len()))
+    }
+
+    fn block_of(&self, index: usize) -> usize {
+        self.position[index] / self.block_size
+    }
+}
+
+/// Run the Cascade protocol, correcting Bob's string towards Alice's.
+///
+/// `qber` (a fraction) sets the first block size to ~0.73/Q, doubling every
+/// pass. Passes after the first work on a random shuffle drawn from `rng`.
+/// Every parity Alice reveals is counted as a leaked bit.
+pub fn run_cascade(alice: &[u8], bob: &[u8], qber: f64, rng: &mut PhotonRng) -> CascadeOutcome {
+    let n = alice.len();
+    let mut corrected = bob.to_vec();
+    let initial_errors = alice.iter().zip(bob).filter(|(a, b)| a != b).count();
+    let initial_block_size = if qber > 0.0 {
+        ((0.73 / qber).ceil() as usize).clamp(4, n.max(4))
+    } else {
+        n.max(4)
+    };
+
+    let mut passes: Vec<Pass> = Vec::with_capacity(CASCADE_PASSES);
+    let mut disclosed_parities = 0;
+
+    if n > 0 {
+        for pass_index in 0..CASCADE_PASSES {
+            let mut order: Vec<usize> = (0..n).collect();
+            if pass_index > 0 {
+                order.shuffle(rng);
+            }
+            let mut position = vec![0; n];
+            for (pos, &index) in order.iter().enumerate() {
+                position[index] = pos;
+            }
+            passes.push(Pass {
+                order,
+                position,
+                block_size: initial_block_size << pass_index,
+            });
+
+            let current = passes.len() - 1;
+            let block_count = n.div_ceil(passes[current].block_size);
+            for top_block in 0..block_count {
+                // Alice announces the parity of every top-level block
+                disclosed_parities += 1;
+                let mut pending = vec![(current, top_block)];
+
+                // Each error fixed flips the parity of the block holding it in
+                // every other pass, which may expose a further error there
+                while let Some((pass, block)) = pending.pop() {
+                    let (start, end) = passes[pass].block_range(block);
+                    if block_parity(alice, &corrected, &passes[pass].order[start..end]) == 0 {
+                        continue;
+                    }
+                    let (index, cost) = binary_search(alice, &corrected, &passes[pass].order[start..end]);
+                    disclosed_parities += cost;
+                    corrected[index] ^= 1;
+                    for (other, other_pass) in passes.iter().enumerate() {
+                        let other_block = other_pass.block_of(index);
+                        // Blocks of this pass not reached yet get checked in turn
+                        if other != pass && !(other == current && other_block > top_block) {
+                            pending.push((other, other_block));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let residual_errors = alice.iter().zip(&corrected).filter(|(a, b)| a != b).count();
+    let shannon_limit = n as f64 * binary_entropy(initial_errors as f64 / n.max(1) as f64);
+    let efficiency = (shannon_limit > 0.0).then(|| disclosed_parities as f64 / shannon_limit);
+
+    CascadeOutcome {
+        corrected,
+        report: ErrorCorrectionReport {
+            passes: passes.len(),
+            initial_block_size,
+            disclosed_parities,
+            corrected_errors: initial_errors.saturating_sub(residual_errors),
+            residual_errors,
+            efficiency,
+        },
+    }
+}
+
+// Parity difference between Alice's and Bob's bits at `indices`
+fn block_parity(alice: &[u8], bob: &[u8], indices: &[usize]) -> u8 {
+    indices.iter().fold(0, |parity, &i| parity ^ alice[i] ^ bob[i])
+}
+
+// Halve a block with odd parity difference until the erroneous bit is found.
+// Returns the key index and the number of parities Alice had to reveal.
+fn binary_search(alice: &[u8], bob: &[u8], mut indices: &[usize]) -> (usize, usize) {
+    let mut cost = 0;
+    while indices.len() > 1 {
+        let (left, right) = indices.split_at(indices.len() / 2);
+        cost += 1;
+        indices = if block_parity(alice, bob, left) == 1 { left } else { right };
+    }
+    (indices[0], cost)
+}
@@ -0,0 +1,310 @@
+use crate::attack::{Announcement, InFlight, transmit, detect};
+use crate::bits::BitVec;
+use crate::key_rate::EC_EFFICIENCY;
+use crate::models::{
+    Basis, HackerConfig, NoiseModel, DecoyConfig, KeyRateBound, DecoyLevel, Pulse, IntensityStats, DecoyReport,
+    PnsReport, PrivacyAmplificationReport,
+};
+use crate::photons::{Photon, PhotonColumns};
+use crate::polarization::Polarization;
+use crate::protocol::{QkdProtocol, ProtocolCore, PARALLEL_THRESHOLD};
+use crate::rng::{PhotonRng, Stream};
+use crate::stats::binary_entropy;
+use rayon::prelude::*;
+
+// BB84 with weak coherent pulses instead of single photons. Every pulse is
+// sent at the signal, decoy or vacuum intensity with a Poisson photon number;
+// only signal pulses enter the key, and the decoy and vacuum statistics bound
+// how much of it came from single photons (Ma, Qi, Zhao and Lo 2005).
+pub struct DecoyBB84Simulator {
+    core: ProtocolCore,
+}
+
+impl DecoyBB84Simulator {
+    pub fn new() -> Self {
+        Self {
+            core: ProtocolCore::new("DECOY"),
+        }
+    }
+}
+
+impl Default for DecoyBB84Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QkdProtocol for DecoyBB84Simulator {
+    fn name(&self) -> &'static str {
+        "decoy-bb84"
+    }
+
+    fn core(&self) -> &ProtocolCore {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut ProtocolCore {
+        &mut self.core
+    }
+
+    fn generate_alice_bits(&mut self, count: usize) -> &PhotonColumns {
+        self.core.generate_alice_bits(count);
+        let config = &self.core.decoy_config;
+        let seed = self.core.state.seed;
+        let draw = |index: usize| {
+            let mut rng = PhotonRng::new(seed, Stream::Intensity, index);
+            draw_pulse(config, &mut rng)
+        };
+
+        // Use parallel processing for large counts
+        self.core.state.pulses = if count > PARALLEL_THRESHOLD {
+            (0..count).into_par_iter().map(draw).collect()
+        } else {
+            (0..count).map(draw).collect()
+        };
+        &self.core.state.alice_bits
+    }
+
+    fn measure_bits(&mut self, hacker_present: bool) -> &PhotonColumns {
+        let hacker_config = self.core.hacker_config.clone();
+        let noise_model = self.core.noise_model.clone();
+        let pulses = std::mem::take(&mut self.core.state.pulses);
+        self.core.measure_with(hacker_present, |index, alice_bit, rng| {
+            measure_pulse(alice_bit, &pulses[index], hacker_present, &hacker_config, &noise_model, rng)
+        });
+        self.core.state.pulses = pulses;
+        &self.core.state.bob_bits
+    }
+
+    // GLLP in `decoy` replaces the single-photon bounds
+    fn key_rate_bound(&self) -> Option<KeyRateBound> {
+        None
+    }
+
+    // Only the single-photon share of the key is secure, see `gllp_length`
+    fn amplify_privacy(&mut self, epsilon: f64) -> PrivacyAmplificationReport {
+        let input_length = self.core.state.alice_corrected_key.len();
+        let output_length = self.core.state.decoy.as_ref().map_or(0, |report| {
+            gllp_length(report, input_length, self.core.leaked_bits(), epsilon)
+        });
+        self.core.amplify_to(output_length, epsilon)
+    }
+
+    // Sift as BB84 but keep only signal pulses; decoy and vacuum results are
+    // disclosed to estimate the single-photon contribution
+    fn sift_key(&mut self) -> &BitVec {
+        let state = &self.core.state;
+        let (alice_bits, bob_bits) = (&state.alice_bits, &state.bob_bits);
+        let sift = |i: usize| {
+            let kept = state.pulses[i].level == DecoyLevel::Signal
+                && bob_bits.is_detected(i)
+                && alice_bits.basis(i) == bob_bits.basis(i);
+            kept.then_some((alice_bits.value(i), bob_bits.value(i)))
+        };
+
+        // Use parallel processing for large counts
+        let count = self.core.sifting_positions();
+        let decisions: Vec<Option<(u8, u8)>> = if count > PARALLEL_THRESHOLD {
+            (0..count).into_par_iter().map(sift).collect()
+        } else {
+            (0..count).map(sift).collect()
+        };
+
+        let report = analyze_decoys(&self.core.decoy_config, &state.pulses, &state.alice_bits, &state.bob_bits);
+        let pns_attack = matches!(self.core.hacker_config, HackerConfig::PhotonNumberSplitting(_));
+        let pns = (state.is_hacker_present && pns_attack)
+            .then(|| pns_report(&decisions, &state.alice_bits, &state.intercepted_bits));
+        self.core.state.decoy = Some(report);
+        self.core.state.pns = pns;
+        self.core.assess_eavesdropper(&decisions);
+        self.core.reconcile(decisions)
+    }
+}
+
+pub fn intensity_of(config: &DecoyConfig, level: DecoyLevel) -> f64 {
+    match level {
+        DecoyLevel::Signal => config.signal_intensity,
+        DecoyLevel::Decoy => config.decoy_intensity,
+        DecoyLevel::Vacuum => 0.0,
+    }
+}
+
+fn draw_pulse(config: &DecoyConfig, rng: &mut PhotonRng) -> Pulse {
+    let choice = rng.next_f64();
+    let level = if choice < config.signal_probability {
+        DecoyLevel::Signal
+    } else if choice < config.signal_probability + config.decoy_probability {
+        DecoyLevel::Decoy
+    } else {
+        DecoyLevel::Vacuum
+    };
+    Pulse {
+        level,
+        photon_number: poisson(intensity_of(config, level), rng),
+    }
+}
+
+// Knuth's multiplication method, fine for the small means used here
+pub fn poisson(mean: f64, rng: &mut PhotonRng) -> u32 {
+    let limit = (-mean).exp();
+    let mut product = rng.next_f64();
+    let mut count = 0;
+    while product > limit {
+        product *= rng.next_f64();
+        count += 1;
+    }
+    count
+}
+
+// Send one pulse through the (possibly tapped) channel to Bob. Every photon
+// is lost or detected on its own, so a multi-photon pulse can hit both
+// detectors when Bob measures in the wrong basis.
+fn measure_pulse(
+    alice_bit: &Photon,
+    pulse: &Pulse,
+    hacker_present: bool,
+    hacker_config: &HackerConfig,
+    noise_model: &NoiseModel,
+    rng: &mut PhotonRng,
+) -> (Photon, Option<Photon>) {
+    let photon = InFlight {
+        polarization: Polarization::from_degrees(alice_bit.polarization),
+        photons: pulse.photon_number,
+    };
+    let attack = hacker_present.then(|| hacker_config.attack());
+    let (arriving, intercepted) =
+        transmit(photon, Some(Announcement::of(alice_bit.basis)), attack, noise_model.loss_probability, &Basis::BB84, rng);
+
+    // Bob's random basis choice
+    let bob_basis = rng.basis(&Basis::BB84);
+    let analyzer = Polarization::of(bob_basis, 0).rotated(noise_model.analyzer_misalignment);
+    let (detection, bob_value) = detect(arriving, analyzer, noise_model, rng);
+
+    let bob_bit = Photon {
+        detection: Some(detection),
+        ..Photon::prepared(bob_basis, bob_value)
+    };
+
+    (bob_bit, intercepted)
+}
+
+// Eve's share of the sifted key under a PNS attack. `decisions` holds the
+// sifted (Alice, Bob) bits by position.
+fn pns_report(decisions: &[Option<(u8, u8)>], alice_bits: &PhotonColumns, intercepted_bits: &PhotonColumns) -> PnsReport {
+    let known_bits = intercepted_bits
+        .iter_positions()
+        .filter_map(|(index, bit)| decisions.get(index).copied().flatten().filter(|_| bit.value == alice_bits.value(index)))
+        .count();
+    let sifted: Vec<&(u8, u8)> = decisions.iter().flatten().collect();
+    let errors = sifted.iter().filter(|(alice, bob)| alice != bob).count();
+
+    PnsReport {
+        split_pulses: intercepted_bits.len(),
+        sifted_bits: sifted.len(),
+        known_bits,
+        information_gain: if sifted.is_empty() { 0.0 } else { known_bits as f64 / sifted.len() as f64 },
+        qber: if sifted.is_empty() { 0.0 } else { errors as f64 / sifted.len() as f64 },
+    }
+}
+
+/// Per-intensity gains and QBERs plus the vacuum + weak decoy bounds.
+///
+/// With Y0 taken from the vacuum gain,
+///   Y1 ≥ μ / (μν − ν²) · (Q_ν e^ν − Q_μ e^μ ν²/μ² − (μ² − ν²)/μ² · Y0)
+///   e1 ≤ (E_ν Q_ν e^ν − Y0 / 2) / (Y1 ν)
+/// and the GLLP rate per signal pulse is
+///   R = ½ · (Q1 (1 − h(e1)) − f Q_μ h(E_μ)),  Q1 = μ e^(−μ) Y1.
+pub fn analyze_decoys(
+    config: &DecoyConfig,
+    pulses: &[Pulse],
+    alice_bits: &PhotonColumns,
+    bob_bits: &PhotonColumns,
+) -> DecoyReport {
+    let intensities: Vec<IntensityStats> = [DecoyLevel::Signal, DecoyLevel::Decoy, DecoyLevel::Vacuum]
+        .into_iter()
+        .map(|level| intensity_stats(config, level, pulses, alice_bits, bob_bits))
+        .collect();
+    let (signal, decoy, vacuum) = (&intensities[0], &intensities[1], &intensities[2]);
+    let (mu, nu) = (signal.intensity, decoy.intensity);
+
+    let background_yield = vacuum.gain;
+    let y1 = if mu > nu && nu > 0.0 {
+        mu / (mu * nu - nu * nu)
+            * (decoy.gain * nu.exp()
+                - signal.gain * mu.exp() * nu * nu / (mu * mu)
+                - (mu * mu - nu * nu) / (mu * mu) * background_yield)
+    } else {
+        0.0
+    };
+    let single_photon_yield = y1.clamp(0.0, 1.0);
+    let single_photon_gain = mu * (-mu).exp() * single_photon_yield;
+    let single_photon_error = if single_photon_yield > 0.0 {
+        ((decoy.qber * decoy.gain * nu.exp() - background_yield / 2.0) / (single_photon_yield * nu)).clamp(0.0, 0.5)
+    } else {
+        0.5
+    };
+
+    let key_rate = 0.5
+        * (single_photon_gain * (1.0 - binary_entropy(single_photon_error))
+            - EC_EFFICIENCY * signal.gain * binary_entropy(signal.qber));
+
+    DecoyReport {
+        intensities,
+        background_yield,
+        single_photon_yield,
+        single_photon_gain,
+        single_photon_error,
+        key_rate: key_rate.max(0.0),
+    }
+}
+
+/// Secure length of an `input_length`-bit signal key (GLLP): of the sifted
+/// signal detections only the share Δ1 = Q1 / Q_μ is known to come from
+/// single photons, and only they can be kept from Eve, so
+///   ℓ = n·Δ1·(1 − h(e1)) − leak_EC − 2·log2(1/ε), floored at 0.
+pub fn gllp_length(report: &DecoyReport, input_length: usize, leaked_bits: usize, epsilon: f64) -> usize {
+    let signal_gain = report.intensities[0].gain;
+    let single_photon_share =
+        if signal_gain > 0.0 { (report.single_photon_gain / signal_gain).min(1.0) } else { 0.0 };
+    let length = input_length as f64 * single_photon_share * (1.0 - binary_entropy(report.single_photon_error))
+        - leaked_bits as f64
+        - 2.0 * (1.0 / epsilon).log2();
+    if length > 0.0 { length.floor() as usize } else { 0 }
+}
+
+fn intensity_stats(
+    config: &DecoyConfig,
+    level: DecoyLevel,
+    pulses: &[Pulse],
+    alice_bits: &PhotonColumns,
+    bob_bits: &PhotonColumns,
+) -> IntensityStats {
+    let (mut count, mut detections, mut sifted, mut errors) = (0, 0, 0, 0);
+    for (i, pulse) in pulses.iter().enumerate().take(bob_bits.len()) {
+        if pulse.level != level {
+            continue;
+        }
+        count += 1;
+        if !bob_bits.is_detected(i) {
+            continue;
+        }
+        detections += 1;
+        if alice_bits.basis(i) == bob_bits.basis(i) {
+            sifted += 1;
+            if alice_bits.value(i) != bob_bits.value(i) {
+                errors += 1;
+            }
+        }
+    }
+
+    IntensityStats {
+        level,
+        intensity: intensity_of(config, level),
+        pulses: count,
+        detections,
+        gain: if count == 0 { 0.0 } else { detections as f64 / count as f64 },
+        sifted,
+        errors,
+        qber: if sifted == 0 { 0.0 } else { errors as f64 / sifted as f64 },
+    }
+}
@@ -0,0 +1,212 @@
+use crate::attack::{Announcement, InFlight, transmit, detect};
+use crate::bits::BitVec;
+use crate::models::{Basis, Phase, HackerConfig, NoiseModel, ChshReport};
+use crate::photons::{Photon, PhotonColumns, Party};
+use crate::polarization::{Polarization, wrap_degrees};
+use crate::protocol::{QkdProtocol, ProtocolCore, PARALLEL_THRESHOLD, now_millis, collect_photons};
+use crate::rng::{PhotonRng, Stream};
+use rayon::prelude::*;
+
+// A source next to Alice emits |Φ+⟩ = (|HH⟩ + |VV⟩)/√2 pairs. Alice and Bob
+// each measure their photon with a linear analyzer at one of three angles:
+pub const ALICE_ANGLES: [f64; 3] = [0.0, 22.5, 45.0];
+pub const BOB_ANGLES: [f64; 3] = [22.5, 45.0, 67.5];
+// Equal angles (2/9 of pairs) give perfectly correlated key bits. Alice's
+// 0°/45° against Bob's 22.5°/67.5° give the CHSH combination
+//   S = E(a1,b1) − E(a1,b3) + E(a3,b1) + E(a3,b3),
+// with E(a,b) = cos 2(a − b), so S = 2√2 for undisturbed pairs while any
+// local hidden-variable model, and any Eve who measures the photons, keeps
+// |S| ≤ 2. Bits carry their analyzer angle in `analyzer_angles`; their
+// `basis` is always rectilinear and their polarization is the angle of the
+// state they were projected onto.
+pub struct E91Simulator {
+    core: ProtocolCore,
+}
+
+impl E91Simulator {
+    pub fn new() -> Self {
+        Self {
+            core: ProtocolCore::new("E91"),
+        }
+    }
+}
+
+impl Default for E91Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QkdProtocol for E91Simulator {
+    fn name(&self) -> &'static str {
+        "e91"
+    }
+
+    fn core(&self) -> &ProtocolCore {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut ProtocolCore {
+        &mut self.core
+    }
+
+    // Emit `count` pairs; Alice measures her half at the source
+    fn generate_alice_bits(&mut self, count: usize) -> &PhotonColumns {
+        let now = now_millis();
+        let seed = self.core.next_seed();
+
+        let choose = |i: usize| {
+            let mut rng = PhotonRng::new(seed, Stream::Preparation, i);
+            let alice_angle = ALICE_ANGLES[setting(&mut rng)];
+            let bob_angle = BOB_ANGLES[setting(&mut rng)];
+            // Either result is equally likely for a maximally entangled pair
+            ([alice_angle, bob_angle], rng.bit())
+        };
+
+        // Use parallel processing for large counts
+        let (angles, values): (Vec<[f64; 2]>, Vec<u8>) = if count > PARALLEL_THRESHOLD {
+            (0..count).into_par_iter().map(choose).unzip()
+        } else {
+            (0..count).map(choose).unzip()
+        };
+        let alice_bits = collect_photons(Party::Alice, now, count, |i| Photon {
+            polarization: projected(angles[i][0], values[i]),
+            ..Photon::prepared(Basis::Rectilinear, values[i])
+        });
+
+        let state = &mut self.core.state;
+        state.alice_bits = alice_bits;
+        state.analyzer_angles = angles;
+        state.start_time = now;
+        state.seed = seed;
+        self.core.set_phase(Phase::Transmission);
+        &self.core.state.alice_bits
+    }
+
+    fn measure_bits(&mut self, hacker_present: bool) -> &PhotonColumns {
+        let hacker_config = self.core.hacker_config.clone();
+        let noise_model = self.core.noise_model.clone();
+        let angles = std::mem::take(&mut self.core.state.analyzer_angles);
+        self.core.measure_with(hacker_present, |index, alice_bit, rng| {
+            measure_pair(index, alice_bit, angles[index], hacker_present, &hacker_config, &noise_model, rng)
+        });
+        self.core.state.analyzer_angles = angles;
+        &self.core.state.bob_bits
+    }
+
+    // Keep equal-angle pairs as key and spend the rest on the CHSH test
+    fn sift_key(&mut self) -> &BitVec {
+        let state = &self.core.state;
+        let sift = |i: usize| {
+            let [alice_angle, bob_angle] = state.analyzer_angles[i];
+            let (alice_bits, bob_bits) = (&state.alice_bits, &state.bob_bits);
+            (bob_bits.is_detected(i) && alice_angle == bob_angle).then_some((alice_bits.value(i), bob_bits.value(i)))
+        };
+
+        // Use parallel processing for large counts
+        let count = self.core.sifting_positions();
+        let decisions: Vec<Option<(u8, u8)>> = if count > PARALLEL_THRESHOLD {
+            (0..count).into_par_iter().map(sift).collect()
+        } else {
+            (0..count).map(sift).collect()
+        };
+
+        let chsh = chsh_test(&state.analyzer_angles, &state.alice_bits, &state.bob_bits);
+        self.core.state.chsh = Some(chsh);
+        self.core.assess_eavesdropper(&decisions);
+        self.core.reconcile(decisions)
+    }
+}
+
+// Uniform choice of one of the three analyzer settings
+fn setting(rng: &mut PhotonRng) -> usize {
+    ((rng.next_f64() * 3.0) as usize).min(2)
+}
+
+// Polarization of the state a result at `angle` projects onto
+fn projected(angle: f64, value: u8) -> f64 {
+    wrap_degrees(angle + 90.0 * value as f64)
+}
+
+// Send Bob's half of the pair through the (possibly tapped) channel. Alice's
+// result has already projected it onto her analyzer's axis.
+fn measure_pair(
+    index: usize,
+    alice_bit: &Photon,
+    [alice_angle, bob_angle]: [f64; 2],
+    hacker_present: bool,
+    hacker_config: &HackerConfig,
+    noise_model: &NoiseModel,
+    rng: &mut PhotonRng,
+) -> (Photon, Option<Photon>) {
+    let drift = noise_model.polarization_drift * index as f64;
+    let photon = InFlight {
+        polarization: Polarization::linear(alice_angle + 90.0 * alice_bit.value as f64 + drift),
+        photons: 1,
+    };
+    let attack = hacker_present.then(|| hacker_config.attack());
+    // Eve's stored photons are read at Alice's announced analyzer angle
+    let announced = Some(Announcement::analyzer(alice_angle));
+    let (arriving, intercepted) =
+        transmit(photon, announced, attack, noise_model.loss_probability, &Basis::BB84, rng);
+    let analyzer = Polarization::linear(bob_angle + noise_model.analyzer_misalignment);
+    let (detection, bob_value) = detect(arriving, analyzer, noise_model, rng);
+
+    let bob_bit = Photon {
+        polarization: projected(bob_angle, bob_value),
+        detection: Some(detection),
+        ..Photon::prepared(Basis::Rectilinear, bob_value)
+    };
+
+    (bob_bit, intercepted)
+}
+
+/// CHSH value from the pairs where Alice used 0°/45° and Bob 22.5°/67.5°.
+///
+/// Each correlation E = (N_same − N_diff) / N has variance (1 − E²) / N, and
+/// the four are independent, so σ_S = √Σ (1 − E²) / N.
+pub fn chsh_test(angles: &[[f64; 2]], alice_bits: &PhotonColumns, bob_bits: &PhotonColumns) -> ChshReport {
+    let settings = [
+        (ALICE_ANGLES[0], BOB_ANGLES[0]),
+        (ALICE_ANGLES[0], BOB_ANGLES[2]),
+        (ALICE_ANGLES[2], BOB_ANGLES[0]),
+        (ALICE_ANGLES[2], BOB_ANGLES[2]),
+    ];
+    let signs = [1.0, -1.0, 1.0, 1.0];
+
+    let mut same = [0usize; 4];
+    let mut samples = [0usize; 4];
+    for (i, &[alice_angle, bob_angle]) in angles.iter().enumerate().take(bob_bits.len()) {
+        if !bob_bits.is_detected(i) {
+            continue;
+        }
+        if let Some(k) = settings.iter().position(|&pair| pair == (alice_angle, bob_angle)) {
+            samples[k] += 1;
+            if alice_bits.value(i) == bob_bits.value(i) {
+                same[k] += 1;
+            }
+        }
+    }
+
+    let correlations: [f64; 4] = std::array::from_fn(|k| {
+        if samples[k] == 0 {
+            0.0
+        } else {
+            (2.0 * same[k] as f64 - samples[k] as f64) / samples[k] as f64
+        }
+    });
+    let s_value = correlations.iter().zip(signs).map(|(e, sign)| sign * e).sum();
+    let variance: f64 = correlations
+        .iter()
+        .zip(samples)
+        .filter(|(_, n)| *n > 0)
+        .map(|(e, n)| (1.0 - e * e) / n as f64)
+        .sum();
+
+    ChshReport {
+        s_value,
+        uncertainty: variance.sqrt(),
+        correlations,
+        samples,
+    }
+}
@@ -0,0 +1,66 @@
+use crate::bits::BitVec;
+use crate::models::ParameterEstimationReport;
+use crate::rng::PhotonRng;
+use crate::stats::{wilson_interval, Z_95};
+use rand::seq::index;
+
+// Share of the sifted key sacrificed for QBER estimation by default
+pub const DEFAULT_SAMPLE_FRACTION: f64 = 0.1;
+
+pub struct EstimationOutcome {
+    pub alice: BitVec, // Alice's key with the sample removed
+    pub bob: BitVec,
+    pub sampled: Vec<bool>, // which sifted positions were disclosed
+    pub report: ParameterEstimationReport,
+}
+
+/// Publicly compare a random sample of the sifted key and discard it.
+///
+/// `true_qber` is the simulator-only error rate over the whole sifted key,
+/// for comparison.
+pub fn estimate_qber(
+    alice: &BitVec,
+    bob: &BitVec,
+    sample_fraction: f64,
+    true_qber: f64,
+    rng: &mut PhotonRng,
+) -> EstimationOutcome {
+    let n = alice.len();
+    let sample_size = ((n as f64) * sample_fraction.clamp(0.0, 1.0)).round() as usize;
+
+    let mut sampled = vec![false; n];
+    for position in index::sample(rng, n, sample_size) {
+        sampled[position] = true;
+    }
+
+    let mut sample_errors = 0;
+    let mut alice_rest = BitVec::with_capacity(n - sample_size);
+    let mut bob_rest = BitVec::with_capacity(n - sample_size);
+    for ((a, b), &is_sample) in alice.iter().zip(bob.iter()).zip(&sampled) {
+        if is_sample {
+            sample_errors += usize::from(a != b);
+        } else {
+            alice_rest.push(a);
+            bob_rest.push(b);
+        }
+    }
+
+    let estimated_qber = if sample_size > 0 { sample_errors as f64 / sample_size as f64 } else { 0.0 };
+    let (lower, upper) = wilson_interval(sample_errors, sample_size, Z_95);
+    let report = ParameterEstimationReport {
+        sample_fraction,
+        sample_size,
+        sample_errors,
+        estimated_qber,
+        confidence_interval: [lower, upper],
+        confidence_level: 0.95,
+        true_qber,
+        remaining_key_length: alice_rest.len(),
+    };
+    EstimationOutcome {
+        alice: alice_rest,
+        bob: bob_rest,
+        sampled,
+        report,
+    }
+}
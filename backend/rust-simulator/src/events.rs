@@ -0,0 +1,60 @@
+use crate::models::{Basis, Phase, Detection};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Photons covered by one published batch
+pub const EVENT_BATCH_SIZE: usize = 256;
+
+// Batches a subscriber may fall behind before it is dropped
+pub const SUBSCRIBER_CAPACITY: usize = 256;
+
+// How long a client may leave a batch unread before it is disconnected
+pub const SUBSCRIBER_SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type EventBatch = Arc<Vec<SimulationEvent>>;
+
+/// Progress of a run, streamed to subscribers as it happens.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SimulationEvent {
+    PhotonEmitted { index: usize, basis: Basis, polarization: f64 },
+    EveIntercepted { index: usize, basis: Basis, value: u8 },
+    BobDetected { index: usize, basis: Basis, value: u8, detection: Detection },
+    BasisReconciled { index: usize, alice_basis: Basis, bob_basis: Basis, kept: bool },
+    PhaseChanged { phase: Phase },
+    KeyFinalized { key_length: usize, error_rate: f64 },
+}
+
+/// Fan-out of event batches to bounded subscriber queues.
+#[derive(Default)]
+pub struct EventSink {
+    subscribers: Vec<mpsc::Sender<EventBatch>>,
+}
+
+impl EventSink {
+    pub fn subscribe(&mut self) -> mpsc::Receiver<EventBatch> {
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_CAPACITY);
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.subscribers.is_empty()
+    }
+
+    /// Deliver a batch to every subscriber, dropping the ones that went away.
+    ///
+    /// Never waits: a subscriber whose queue is full has fallen
+    /// `SUBSCRIBER_CAPACITY` batches behind and is dropped too. Steps run with
+    /// the simulator locked, so waiting for one slow client would hold up
+    /// every other client of the simulator.
+    pub fn publish(&mut self, events: Vec<SimulationEvent>) {
+        if events.is_empty() || self.subscribers.is_empty() {
+            return;
+        }
+        let batch = Arc::new(events);
+        self.subscribers.retain(|sender| sender.try_send(batch.clone()).is_ok());
+    }
+}
@@ -0,0 +1,58 @@
+use crate::bits::BitVec;
+use crate::models::InformationReport;
+use crate::photons::{Photon, PhotonColumns};
+use crate::rng::{PhotonRng, Stream};
+use crate::stats::mutual_information;
+
+// Eve's guess of the key bit at every position she holds a record for;
+// `key_bit` reads the guess off her record
+pub fn eve_view<F>(positions: usize, intercepted_bits: &PhotonColumns, key_bit: F) -> Vec<Option<u8>>
+where
+    F: Fn(usize, &Photon) -> u8,
+{
+    let mut view = vec![None; positions];
+    for (index, bit) in intercepted_bits.iter_positions().filter(|&(index, _)| index < positions) {
+        view[index] = Some(key_bit(index, &bit));
+    }
+    view
+}
+
+/// I(A:B), I(A:E) and I(B:E) over the sifted positions, in bits per sifted bit.
+///
+/// Positions Eve holds no record for count as a third outcome on her side,
+/// so a partial attack only earns her information where she attacked.
+pub fn information_report(decisions: &[Option<(u8, u8)>], eve_view: &[Option<u8>]) -> InformationReport {
+    let mut alice_bob = [[0usize; 3]; 2];
+    let mut alice_eve = [[0usize; 3]; 2];
+    let mut bob_eve = [[0usize; 3]; 2];
+    for (decision, eve) in decisions.iter().zip(eve_view) {
+        if let Some((alice, bob)) = *decision {
+            let eve = eve.map_or(2, usize::from);
+            alice_bob[alice as usize][bob as usize] += 1;
+            alice_eve[alice as usize][eve] += 1;
+            bob_eve[bob as usize][eve] += 1;
+        }
+    }
+
+    let alice_bob = mutual_information(&alice_bob);
+    let alice_eve = mutual_information(&alice_eve);
+    InformationReport {
+        alice_bob,
+        alice_eve,
+        bob_eve: mutual_information(&bob_eve),
+        csiszar_korner: alice_bob > alice_eve,
+        eve_final_key_error_rate: None,
+    }
+}
+
+// Eve's best guess of the sifted key: her record where she has one, a coin
+// flip elsewhere
+pub fn eve_guess(decisions: &[Option<(u8, u8)>], eve_view: &[Option<u8>], seed: u64) -> BitVec {
+    decisions
+        .iter()
+        .zip(eve_view)
+        .enumerate()
+        .filter(|(_, (decision, _))| decision.is_some())
+        .map(|(index, (_, eve))| eve.unwrap_or_else(|| PhotonRng::new(seed, Stream::Eavesdropper, index).bit()))
+        .collect()
+}
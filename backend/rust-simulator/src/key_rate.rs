@@ -0,0 +1,142 @@
+use crate::models::{SimulationState, KeyRateBound, KeyRateReport, FluctuationBound, FiniteKeyReport};
+use crate::privacy::DEFAULT_SECURITY_PARAMETER;
+use crate::stats::binary_entropy;
+
+// Cascade's typical leakage relative to the Shannon limit
+pub const EC_EFFICIENCY: f64 = 1.16;
+
+// Failure probability of parameter estimation, ε_PE
+pub const EPSILON_PE: f64 = 1e-10;
+
+// Probability that Alice's and Bob's final keys differ undetected, ε_cor
+pub const EPSILON_COR: f64 = 1e-15;
+
+// Concentration bounds every finite-key length is evaluated with
+const FLUCTUATION_BOUNDS: [FluctuationBound; 2] = [FluctuationBound::Serfling, FluctuationBound::Hoeffding];
+
+/// Upper bound on what Eve may know per key bit at bit error `qber`, so
+/// that the asymptotic one-way rate is r = 1 − h(Q) − leak_E(Q).
+///
+///   BB84 (Shor–Preskill): leak_E = h(Q), r = 1 − 2h(Q)
+///   six-state (Lo 2001):  leak_E = H(1 − 3Q/2, Q/2, Q/2, Q/2) − h(Q)
+pub fn eve_information(bound: KeyRateBound, qber: f64) -> f64 {
+    let qber = qber.clamp(0.0, 0.5);
+    match bound {
+        KeyRateBound::ShorPreskill => binary_entropy(qber),
+        KeyRateBound::SixState => {
+            let bell_diagonal = [1.0 - 1.5 * qber, qber / 2.0, qber / 2.0, qber / 2.0];
+            let entropy: f64 = bell_diagonal.iter().filter(|&&p| p > 0.0).map(|&p| -p * p.log2()).sum();
+            entropy - binary_entropy(qber)
+        }
+    }
+}
+
+// Secret bits per sifted bit in the infinite-key limit, floored at 0
+pub fn asymptotic_rate(bound: KeyRateBound, qber: f64) -> f64 {
+    (1.0 - binary_entropy(qber.clamp(0.0, 0.5)) - eve_information(bound, qber)).max(0.0)
+}
+
+/// How far the error rate of the `n` kept bits may exceed the rate seen on
+/// the `k` sampled bits, except with probability ε_PE.
+///
+/// A sample of k out of N = n + k bits drawn without replacement deviates
+/// from the whole by t with probability at most exp(−2kt²) (Hoeffding), or
+/// exp(−2kt² / (1 − (k − 1)/N)) (Serfling). The kept bits then deviate from
+/// the sample by N/n · t.
+pub fn phase_error_deviation(bound: FluctuationBound, n: usize, k: usize, epsilon_pe: f64) -> f64 {
+    if n == 0 || k == 0 {
+        return 0.5;
+    }
+    let (n, k) = (n as f64, k as f64);
+    let total = n + k;
+    let correction = match bound {
+        FluctuationBound::Hoeffding => 1.0,
+        FluctuationBound::Serfling => 1.0 - (k - 1.0) / total,
+    };
+    total / n * ((1.0 / epsilon_pe).ln() * correction / (2.0 * k)).sqrt()
+}
+
+/// Finite-key length for the block left after parameter estimation:
+///   ℓ = n·(1 − leak_E(Q + μ)) − leak_EC − 2·log2(1/ε_PA) − log2(2/ε_cor)
+/// with the real Cascade leakage once error correction ran, f·n·h(Q) before.
+pub fn finite_key(
+    bound: KeyRateBound,
+    fluctuation: FluctuationBound,
+    state: &SimulationState,
+    qber: f64,
+    epsilon_pa: f64,
+) -> Option<FiniteKeyReport> {
+    let estimation = state.parameter_estimation.as_ref()?;
+    let n = estimation.remaining_key_length;
+    let k = estimation.sample_size;
+
+    let deviation = phase_error_deviation(fluctuation, n, k, EPSILON_PE);
+    let phase_error_bound = (qber + deviation).min(0.5);
+    let leaked_bits = match &state.error_correction {
+        Some(report) => report.disclosed_parities,
+        None => (EC_EFFICIENCY * n as f64 * binary_entropy(qber.min(0.5))).ceil() as usize,
+    };
+
+    let length = n as f64 * (1.0 - eve_information(bound, phase_error_bound))
+        - leaked_bits as f64
+        - 2.0 * (1.0 / epsilon_pa).log2()
+        - (2.0 / EPSILON_COR).log2();
+    let key_length = if length > 0.0 { length.floor() as usize } else { 0 };
+
+    Some(FiniteKeyReport {
+        fluctuation_bound: fluctuation,
+        block_size: n,
+        sample_size: k,
+        phase_error_deviation: deviation,
+        phase_error_bound,
+        leaked_bits,
+        epsilon_pe: EPSILON_PE,
+        epsilon_pa,
+        epsilon_cor: EPSILON_COR,
+        key_length,
+        rate: if n == 0 { 0.0 } else { key_length as f64 / n as f64 },
+    })
+}
+
+/// Secret key length privacy amplification may keep under `bound`: the
+/// shortest `finite_key` length over the fluctuation bounds. None before
+/// parameter estimation, when there is no sample to bound the phase error.
+pub fn finite_key_length(bound: KeyRateBound, state: &SimulationState, epsilon_pa: f64) -> Option<usize> {
+    let qber = estimated_qber(state);
+    FLUCTUATION_BOUNDS
+        .into_iter()
+        .filter_map(|fluctuation| finite_key(bound, fluctuation, state, qber, epsilon_pa))
+        .map(|report| report.key_length)
+        .min()
+}
+
+/// Asymptotic and finite-key rates for the run in `state`, evaluated at
+/// the QBER Alice and Bob estimated.
+pub fn key_rate_report(bound: KeyRateBound, state: &SimulationState) -> KeyRateReport {
+    let qber = estimated_qber(state);
+    let epsilon_pa = state
+        .privacy_amplification
+        .as_ref()
+        .map_or(DEFAULT_SECURITY_PARAMETER, |report| report.security_parameter);
+    let asymptotic = asymptotic_rate(bound, qber);
+    let sifted_fraction = state.sifting.as_ref().map_or(0.0, |sifting| sifting.sifted_fraction);
+
+    KeyRateReport {
+        bound,
+        qber,
+        asymptotic_rate: asymptotic,
+        asymptotic_rate_per_pulse: asymptotic * sifted_fraction,
+        finite_key: FLUCTUATION_BOUNDS
+            .into_iter()
+            .filter_map(|fluctuation| finite_key(bound, fluctuation, state, qber, epsilon_pa))
+            .collect(),
+    }
+}
+
+// The sampled QBER, or the full comparison when estimation was skipped
+fn estimated_qber(state: &SimulationState) -> f64 {
+    match &state.parameter_estimation {
+        Some(estimate) => estimate.estimated_qber,
+        None => state.error_rate / 100.0,
+    }
+}
@@ -1,7 +1,40 @@
+pub mod attack;
+pub mod b92;
+pub mod bbm92;
+pub mod bits;
+pub mod cascade;
+pub mod decoy;
+pub mod e91;
+pub mod estimation;
+pub mod events;
+pub mod information;
+pub mod key_rate;
 pub mod models;
+pub mod photons;
+pub mod polarization;
+pub mod privacy;
+pub mod protocol;
+pub mod rng;
 pub mod simulator;
+pub mod stats;
 pub mod sarg04;
+pub mod six_state;
+pub mod session;
+pub mod sweep;
 
-pub use models::{QuantumBit, SimulationState, HackerConfig, Basis, Phase};
+pub use models::{QuantumBit, Detection, DetectionStats, SimulationState, HackerConfig, Basis, Phase, NoiseModel, FiberChannel, SourcePosition, DecoyConfig, DecoyReport, PnsReport, AttackReport, InformationReport, KeyRateBound, KeyRateReport, FiniteKeyReport, ErrorCorrectionReport, PrivacyAmplificationReport,
+    ParameterEstimationReport, AbortReason, ChshReport, StateSummary, StateWindow};
+pub use attack::Attack;
+pub use bits::BitVec;
+pub use photons::{Photon, PhotonColumns};
+pub use events::{SimulationEvent, EventBatch};
+pub use protocol::{QkdProtocol, ProtocolCore};
 pub use simulator::BB84Simulator;
 pub use sarg04::SARG04Simulator;
+pub use b92::B92Simulator;
+pub use six_state::SixStateSimulator;
+pub use e91::E91Simulator;
+pub use bbm92::BBM92Simulator;
+pub use decoy::DecoyBB84Simulator;
+pub use session::{SessionRegistry, SessionConfig, SessionError, ProtocolKind, SharedProtocol};
+pub use sweep::{SweepRequest, SweepResult, SweepError, run_sweep};
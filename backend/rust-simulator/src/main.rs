@@ -1,15 +1,118 @@
-use qkd_simulator::{BB84Simulator, SARG04Simulator, HackerConfig};
-use qkd_simulator::models::NoiseModel;
+// The nested filter types of the step routes outgrow the default limit in release builds
+#![recursion_limit = "256"]
+
+use futures::{SinkExt, StreamExt};
+use qkd_simulator::{
+    BB84Simulator, SARG04Simulator, B92Simulator, SixStateSimulator, E91Simulator, BBM92Simulator,
+    DecoyBB84Simulator, HackerConfig, NoiseModel, FiberChannel, SourcePosition, DecoyConfig, QkdProtocol, SimulationState,
+    SessionRegistry, SessionConfig, SessionError, ProtocolKind, SharedProtocol, SweepRequest, SweepError, run_sweep,
+};
+use qkd_simulator::estimation::DEFAULT_SAMPLE_FRACTION;
+use qkd_simulator::events::SUBSCRIBER_SEND_TIMEOUT;
+use qkd_simulator::privacy::DEFAULT_SECURITY_PARAMETER;
+use serde::Deserialize;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket, Ws};
 use warp::Filter;
 
+type Registry = Arc<Mutex<SessionRegistry>>;
+
+// Upper bound on photons one run may generate, as `MAX_SWEEP_PHOTONS` bounds a sweep
+const MAX_RUN_PHOTONS: usize = 10_000_000;
+
+// Query parameters for `.../generate/{count}`
+#[derive(Debug, Deserialize)]
+struct GenerateOptions {
+    seed: Option<u64>,
+}
+
+// Query parameters for `.../estimate`
+#[derive(Debug, Deserialize)]
+struct EstimateOptions {
+    fraction: Option<f64>,
+}
+
+// Query parameters for `.../amplify`
+#[derive(Debug, Deserialize)]
+struct AmplifyOptions {
+    epsilon: Option<f64>,
+}
+
+// Query parameters for `.../state`
+#[derive(Debug, Deserialize)]
+struct StateOptions {
+    #[serde(default)]
+    summary: bool, // counts, QBER and key length only
+    offset: Option<usize>,
+    limit: Option<usize>,
+    arrays: Option<bool>, // false leaves the per-position arrays empty
+}
+
+// Body of `.../configure-threshold`; null restores the protocol default
+#[derive(Debug, Deserialize)]
+struct ThresholdConfig {
+    qber_threshold: Option<f64>,
+}
+
+// Body of `.../configure-source`
+#[derive(Debug, Deserialize)]
+struct SourceConfig {
+    position: SourcePosition,
+}
+
+// Body of `POST /sessions`
+#[derive(Debug, Deserialize)]
+struct CreateSessionRequest {
+    protocol: ProtocolKind,
+    bit_count: Option<usize>,
+    seed: Option<u64>,
+}
+
+#[derive(Debug)]
+struct SessionNotFound;
+
+impl warp::reject::Reject for SessionNotFound {}
+
+#[derive(Debug)]
+struct SessionRejected(SessionError);
+
+impl warp::reject::Reject for SessionRejected {}
+
+#[derive(Debug)]
+struct SweepRejected(SweepError);
+
+impl warp::reject::Reject for SweepRejected {}
+
+#[derive(Debug)]
+struct StepFailed;
+
+impl warp::reject::Reject for StepFailed {}
+
+#[derive(Debug)]
+struct TooManyPhotons;
+
+impl warp::reject::Reject for TooManyPhotons {}
+
 #[tokio::main]
 async fn main() {
-    // Initialize simulators
-    let bb84_simulator = Arc::new(Mutex::new(BB84Simulator::new()));
-    let sarg04_simulator = Arc::new(Mutex::new(SARG04Simulator::new()));
-    
+    let registry: Registry = Arc::new(Mutex::new(SessionRegistry::new(session_config_from_env())));
+
+    // Evict idle sessions in the background
+    let eviction_registry = registry.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            let evicted = eviction_registry.lock().await.evict_idle();
+            if evicted > 0 {
+                println!("Evicted {} idle session(s)", evicted);
+            }
+        }
+    });
+
     // CORS configuration
     let cors = warp::cors()
         .allow_any_origin()
@@ -20,208 +123,358 @@ async fn main() {
         .allow_method("DELETE")
         .build();
 
-    // BB84 Routes
-    let bb84_simulator_clone = bb84_simulator.clone();
-    let bb84_generate_route = warp::path("bb84")
+    // Health check route
+    let health_route = warp::path("health")
+        .and(warp::get())
+        .and_then(health_handler);
+
+    // Parameter sweep route
+    let sweep_route = warp::path("sweep")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(sweep_handler);
+
+    // Combine routes
+    let api = protocol_routes(BB84Simulator::new())
+        .or(protocol_routes(SARG04Simulator::new()))
+        .or(protocol_routes(B92Simulator::new()))
+        .or(protocol_routes(SixStateSimulator::new()))
+        .or(protocol_routes(E91Simulator::new()))
+        .or(protocol_routes(BBM92Simulator::new()))
+        .or(protocol_routes(DecoyBB84Simulator::new()))
+        .or(session_routes(registry))
+        .or(sweep_route)
+        .or(health_route)
+        .recover(handle_rejection)
+        .with(cors);
+
+    // Start server
+    println!("Starting QKD Simulator server on port 3030");
+    warp::serve(api).run(([127, 0, 0, 1], 3030)).await;
+}
+
+// Limits can be tuned with QKD_MAX_SESSIONS and QKD_SESSION_TTL_SECS
+fn session_config_from_env() -> SessionConfig {
+    let mut config = SessionConfig::default();
+    if let Some(max_sessions) = env_number("QKD_MAX_SESSIONS") {
+        config.max_sessions = max_sessions as usize;
+    }
+    if let Some(ttl_secs) = env_number("QKD_SESSION_TTL_SECS") {
+        config.idle_ttl = Duration::from_secs(ttl_secs);
+    }
+    config
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    std::env::var(name).ok()?.parse().ok()
+}
+
+// Shared simulator routes for a protocol under `/<name>/...`
+fn protocol_routes(
+    protocol: impl QkdProtocol + 'static,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let prefix = protocol.name();
+    let simulator: SharedProtocol = Arc::new(Mutex::new(protocol));
+    step_routes(warp::path(prefix).and(with_simulator(simulator)))
+}
+
+// Session lifecycle plus per-session step routes under `/sessions/{id}/...`
+fn session_routes(
+    registry: Registry,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let create_route = warp::path("sessions")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_registry(registry.clone()))
+        .and_then(create_session_handler);
+
+    let get_route = warp::path!("sessions" / String)
+        .and(warp::get())
+        .and(with_registry(registry.clone()))
+        .and_then(get_session_handler);
+
+    let delete_route = warp::path!("sessions" / String)
+        .and(warp::delete())
+        .and(with_registry(registry.clone()))
+        .and_then(delete_session_handler);
+
+    let session = warp::path("sessions")
+        .and(warp::path::param::<String>())
+        .and(with_registry(registry))
+        .and_then(lookup_session);
+
+    create_route
+        .or(get_route)
+        .or(delete_route)
+        .or(step_routes(session))
+}
+
+// The protocol step routes, mounted below a filter that resolves the simulator
+fn step_routes<F>(
+    simulator: F,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+    F: Filter<Extract = (SharedProtocol,), Error = warp::Rejection> + Clone + Send + Sync + 'static,
+{
+    let generate_route = simulator.clone()
         .and(warp::path("generate"))
         .and(warp::path::param::<usize>())
         .and(warp::post())
-        .and(with_simulator(bb84_simulator_clone))
+        .and(warp::query::<GenerateOptions>())
         .and_then(generate_bits_handler);
 
-    let bb84_simulator_clone = bb84_simulator.clone();
-    let bb84_measure_route = warp::path("bb84")
+    let measure_route = simulator.clone()
         .and(warp::path("measure"))
         .and(warp::post())
         .and(warp::body::json())
-        .and(with_simulator(bb84_simulator_clone))
         .and_then(measure_bits_handler);
 
-    let bb84_simulator_clone = bb84_simulator.clone();
-    let bb84_sift_route = warp::path("bb84")
+    let sift_route = simulator.clone()
         .and(warp::path("sift"))
         .and(warp::post())
-        .and(with_simulator(bb84_simulator_clone))
         .and_then(sift_key_handler);
 
-    let bb84_simulator_clone = bb84_simulator.clone();
-    let bb84_complete_route = warp::path("bb84")
+    let estimate_route = simulator.clone()
+        .and(warp::path("estimate"))
+        .and(warp::post())
+        .and(warp::query::<EstimateOptions>())
+        .and_then(estimate_parameters_handler);
+
+    let correct_route = simulator.clone()
+        .and(warp::path("correct"))
+        .and(warp::post())
+        .and_then(correct_errors_handler);
+
+    let amplify_route = simulator.clone()
+        .and(warp::path("amplify"))
+        .and(warp::post())
+        .and(warp::query::<AmplifyOptions>())
+        .and_then(amplify_privacy_handler);
+
+    let complete_route = simulator.clone()
         .and(warp::path("complete"))
         .and(warp::post())
-        .and(with_simulator(bb84_simulator_clone))
         .and_then(complete_simulation_handler);
 
-    let bb84_simulator_clone = bb84_simulator.clone();
-    let bb84_reset_route = warp::path("bb84")
+    let reset_route = simulator.clone()
         .and(warp::path("reset"))
         .and(warp::post())
-        .and(with_simulator(bb84_simulator_clone))
         .and_then(reset_handler);
 
-    let bb84_simulator_clone = bb84_simulator.clone();
-    let bb84_configure_hacker_route = warp::path("bb84")
+    let configure_hacker_route = simulator.clone()
         .and(warp::path("configure-hacker"))
         .and(warp::post())
         .and(warp::body::json())
-        .and(with_simulator(bb84_simulator_clone))
         .and_then(configure_hacker_handler);
 
-    let bb84_simulator_clone = bb84_simulator.clone();
-    let bb84_configure_noise_route = warp::path("bb84")
+    let configure_noise_route = simulator.clone()
         .and(warp::path("configure-noise"))
         .and(warp::post())
         .and(warp::body::json())
-        .and(with_simulator(bb84_simulator_clone))
         .and_then(configure_noise_handler);
 
-    let bb84_simulator_clone = bb84_simulator.clone();
-    let bb84_state_route = warp::path("bb84")
-        .and(warp::path("state"))
-        .and(warp::get())
-        .and(with_simulator(bb84_simulator_clone))
-        .and_then(get_state_handler);
-
-    // SARG04 Routes
-    let sarg04_simulator_clone = sarg04_simulator.clone();
-    let sarg04_generate_route = warp::path("sarg04")
-        .and(warp::path("generate"))
-        .and(warp::path::param::<usize>())
-        .and(warp::post())
-        .and(with_sarg04_simulator(sarg04_simulator_clone))
-        .and_then(sarg04_generate_bits_handler);
-
-    let sarg04_simulator_clone = sarg04_simulator.clone();
-    let sarg04_measure_route = warp::path("sarg04")
-        .and(warp::path("measure"))
+    let configure_channel_route = simulator.clone()
+        .and(warp::path("configure-channel"))
         .and(warp::post())
         .and(warp::body::json())
-        .and(with_sarg04_simulator(sarg04_simulator_clone))
-        .and_then(sarg04_measure_bits_handler);
-
-    let sarg04_simulator_clone = sarg04_simulator.clone();
-    let sarg04_sift_route = warp::path("sarg04")
-        .and(warp::path("sift"))
-        .and(warp::post())
-        .and(with_sarg04_simulator(sarg04_simulator_clone))
-        .and_then(sarg04_sift_key_handler);
+        .and_then(configure_channel_handler);
 
-    let sarg04_simulator_clone = sarg04_simulator.clone();
-    let sarg04_complete_route = warp::path("sarg04")
-        .and(warp::path("complete"))
-        .and(warp::post())
-        .and(with_sarg04_simulator(sarg04_simulator_clone))
-        .and_then(sarg04_complete_simulation_handler);
-
-    let sarg04_simulator_clone = sarg04_simulator.clone();
-    let sarg04_reset_route = warp::path("sarg04")
-        .and(warp::path("reset"))
+    let configure_threshold_route = simulator.clone()
+        .and(warp::path("configure-threshold"))
         .and(warp::post())
-        .and(with_sarg04_simulator(sarg04_simulator_clone))
-        .and_then(sarg04_reset_handler);
+        .and(warp::body::json())
+        .and_then(configure_threshold_handler);
 
-    let sarg04_simulator_clone = sarg04_simulator.clone();
-    let sarg04_configure_hacker_route = warp::path("sarg04")
-        .and(warp::path("configure-hacker"))
+    let configure_source_route = simulator.clone()
+        .and(warp::path("configure-source"))
         .and(warp::post())
         .and(warp::body::json())
-        .and(with_sarg04_simulator(sarg04_simulator_clone))
-        .and_then(sarg04_configure_hacker_handler);
+        .and_then(configure_source_handler);
 
-    let sarg04_simulator_clone = sarg04_simulator.clone();
-    let sarg04_configure_noise_route = warp::path("sarg04")
-        .and(warp::path("configure-noise"))
+    let configure_decoy_route = simulator.clone()
+        .and(warp::path("configure-decoy"))
         .and(warp::post())
         .and(warp::body::json())
-        .and(with_sarg04_simulator(sarg04_simulator_clone))
-        .and_then(sarg04_configure_noise_handler);
+        .and_then(configure_decoy_handler);
 
-    let sarg04_simulator_clone = sarg04_simulator.clone();
-    let sarg04_state_route = warp::path("sarg04")
+    let state_route = simulator.clone()
         .and(warp::path("state"))
         .and(warp::get())
-        .and(with_sarg04_simulator(sarg04_simulator_clone))
-        .and_then(sarg04_get_state_handler);
+        .and(warp::query::<StateOptions>())
+        .and_then(get_state_handler);
 
-    // Health check route
-    let health_route = warp::path("health")
-        .and(warp::get())
-        .and_then(health_handler);
+    let events_route = simulator
+        .and(warp::path("events"))
+        .and(warp::ws())
+        .map(|simulator: SharedProtocol, ws: Ws| {
+            ws.on_upgrade(move |socket| stream_events(simulator, socket))
+        });
+
+    generate_route
+        .or(measure_route)
+        .or(sift_route)
+        .or(estimate_route)
+        .or(correct_route)
+        .or(amplify_route)
+        .or(complete_route)
+        .or(reset_route)
+        .or(configure_hacker_route)
+        .or(configure_noise_route)
+        .or(configure_channel_route)
+        .or(configure_threshold_route)
+        .or(configure_source_route)
+        .or(configure_decoy_route)
+        .or(state_route)
+        .or(events_route)
+}
 
-    // Combine routes
-    let api = bb84_generate_route
-        .or(bb84_measure_route)
-        .or(bb84_sift_route)
-        .or(bb84_complete_route)
-        .or(bb84_reset_route)
-        .or(bb84_configure_hacker_route)
-        .or(bb84_configure_noise_route)
-        .or(bb84_state_route)
-        .or(sarg04_generate_route)
-        .or(sarg04_measure_route)
-        .or(sarg04_sift_route)
-        .or(sarg04_complete_route)
-        .or(sarg04_reset_route)
-        .or(sarg04_configure_hacker_route)
-        .or(sarg04_configure_noise_route)
-        .or(sarg04_state_route)
-        .or(health_route)
-        .with(cors);
+// Session handler functions
+async fn create_session_handler(
+    request: CreateSessionRequest,
+    registry: Registry,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bit_count = request.bit_count;
+    check_photon_count(bit_count.unwrap_or(0))?;
+    let (_, simulator) = registry
+        .lock()
+        .await
+        .create(request.protocol, request.seed)
+        .await
+        .map_err(|err| warp::reject::custom(SessionRejected(err)))?;
+
+    let reply = run_step(simulator, move |sim| {
+        if let Some(bit_count) = bit_count {
+            sim.generate_alice_bits(bit_count);
+        }
+        sim.get_state()
+    })
+    .await?;
+    Ok(warp::reply::with_status(reply, StatusCode::CREATED))
+}
 
-    // Start server
-    println!("Starting QKD Simulator server on port 3030");
-    warp::serve(api).run(([127, 0, 0, 1], 3030)).await;
+async fn get_session_handler(
+    session_id: String,
+    registry: Registry,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let simulator = lookup_session(session_id, registry).await?;
+    let sim = simulator.lock().await;
+    let state = sim.get_state();
+    Ok(warp::reply::json(&state))
+}
+
+async fn delete_session_handler(
+    session_id: String,
+    registry: Registry,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if registry.lock().await.remove(&session_id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(warp::reject::custom(SessionNotFound))
+    }
+}
+
+async fn lookup_session(
+    session_id: String,
+    registry: Registry,
+) -> Result<SharedProtocol, warp::Rejection> {
+    registry
+        .lock()
+        .await
+        .get(&session_id)
+        .ok_or_else(|| warp::reject::custom(SessionNotFound))
 }
 
-// BB84 Handler functions
+// Step handler functions
 async fn generate_bits_handler(
+    simulator: SharedProtocol,
     count: usize,
-    simulator: Arc<Mutex<BB84Simulator>>,
+    options: GenerateOptions,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut sim = simulator.lock().await;
-    sim.generate_alice_bits(count);
-    let state = sim.get_state();
-    Ok(warp::reply::json(&state))
+    check_photon_count(count)?;
+    run_step(simulator, move |sim| {
+        if let Some(seed) = options.seed {
+            sim.set_seed(seed);
+        }
+        sim.generate_alice_bits(count);
+        sim.get_state()
+    })
+    .await
 }
 
 async fn measure_bits_handler(
+    simulator: SharedProtocol,
     hacker_present: bool,
-    simulator: Arc<Mutex<BB84Simulator>>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut sim = simulator.lock().await;
-    sim.measure_bits(hacker_present);
-    let state = sim.get_state();
-    Ok(warp::reply::json(&state))
+    run_step(simulator, move |sim| {
+        sim.measure_bits(hacker_present);
+        sim.get_state()
+    })
+    .await
 }
 
 async fn sift_key_handler(
-    simulator: Arc<Mutex<BB84Simulator>>,
+    simulator: SharedProtocol,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut sim = simulator.lock().await;
-    sim.sift_key();
-    let state = sim.get_state();
-    Ok(warp::reply::json(&state))
+    run_step(simulator, |sim| {
+        sim.sift_key();
+        sim.get_state()
+    })
+    .await
+}
+
+async fn estimate_parameters_handler(
+    simulator: SharedProtocol,
+    options: EstimateOptions,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    run_step(simulator, move |sim| {
+        sim.estimate_parameters(options.fraction.unwrap_or(DEFAULT_SAMPLE_FRACTION));
+        sim.get_state()
+    })
+    .await
+}
+
+async fn correct_errors_handler(
+    simulator: SharedProtocol,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    run_step(simulator, |sim| {
+        sim.correct_errors();
+        sim.get_state()
+    })
+    .await
+}
+
+async fn amplify_privacy_handler(
+    simulator: SharedProtocol,
+    options: AmplifyOptions,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    run_step(simulator, move |sim| {
+        sim.amplify_privacy(options.epsilon.unwrap_or(DEFAULT_SECURITY_PARAMETER));
+        sim.get_state()
+    })
+    .await
 }
 
 async fn complete_simulation_handler(
-    simulator: Arc<Mutex<BB84Simulator>>,
+    simulator: SharedProtocol,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut sim = simulator.lock().await;
-    let state = sim.complete_simulation();
-    Ok(warp::reply::json(&state))
+    run_step(simulator, |sim| sim.complete_simulation()).await
 }
 
 async fn reset_handler(
-    simulator: Arc<Mutex<BB84Simulator>>,
+    simulator: SharedProtocol,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut sim = simulator.lock().await;
-    sim.reset();
-    let state = sim.get_state();
-    Ok(warp::reply::json(&state))
+    run_step(simulator, |sim| {
+        sim.reset();
+        sim.get_state()
+    })
+    .await
 }
 
 async fn configure_hacker_handler(
+    simulator: SharedProtocol,
     config: HackerConfig,
-    simulator: Arc<Mutex<BB84Simulator>>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut sim = simulator.lock().await;
     sim.configure_hacker(config);
@@ -230,8 +483,8 @@ async fn configure_hacker_handler(
 }
 
 async fn configure_noise_handler(
-    noise_model: qkd_simulator::simulator::NoiseModel,
-    simulator: Arc<Mutex<BB84Simulator>>,
+    simulator: SharedProtocol,
+    noise_model: NoiseModel,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut sim = simulator.lock().await;
     sim.configure_noise(noise_model);
@@ -239,87 +492,124 @@ async fn configure_noise_handler(
     Ok(warp::reply::json(&state))
 }
 
-async fn get_state_handler(
-    simulator: Arc<Mutex<BB84Simulator>>,
+async fn configure_channel_handler(
+    simulator: SharedProtocol,
+    channel: FiberChannel,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let sim = simulator.lock().await;
+    let mut sim = simulator.lock().await;
+    sim.configure_channel(channel);
     let state = sim.get_state();
     Ok(warp::reply::json(&state))
 }
 
-// SARG04 Handler functions
-async fn sarg04_generate_bits_handler(
-    count: usize,
-    simulator: Arc<Mutex<SARG04Simulator>>,
+async fn configure_threshold_handler(
+    simulator: SharedProtocol,
+    config: ThresholdConfig,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut sim = simulator.lock().await;
-    sim.generate_alice_bits(count);
+    sim.set_qber_threshold(config.qber_threshold);
     let state = sim.get_state();
     Ok(warp::reply::json(&state))
 }
 
-async fn sarg04_measure_bits_handler(
-    hacker_present: bool,
-    simulator: Arc<Mutex<SARG04Simulator>>,
+async fn configure_source_handler(
+    simulator: SharedProtocol,
+    config: SourceConfig,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut sim = simulator.lock().await;
-    sim.measure_bits(hacker_present);
+    sim.configure_source(config.position);
     let state = sim.get_state();
     Ok(warp::reply::json(&state))
 }
 
-async fn sarg04_sift_key_handler(
-    simulator: Arc<Mutex<SARG04Simulator>>,
+async fn configure_decoy_handler(
+    simulator: SharedProtocol,
+    config: DecoyConfig,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut sim = simulator.lock().await;
-    sim.sift_key();
+    sim.configure_decoy(config);
     let state = sim.get_state();
     Ok(warp::reply::json(&state))
 }
 
-async fn sarg04_complete_simulation_handler(
-    simulator: Arc<Mutex<SARG04Simulator>>,
+async fn get_state_handler(
+    simulator: SharedProtocol,
+    options: StateOptions,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut sim = simulator.lock().await;
-    let state = sim.complete_simulation();
+    if options.summary {
+        return Ok(warp::reply::json(&sim.get_summary()));
+    }
+
+    let state = if options.arrays == Some(false) {
+        sim.get_state_window(0, 0)
+    } else if options.offset.is_some() || options.limit.is_some() {
+        sim.get_state_window(options.offset.unwrap_or(0), options.limit.unwrap_or(usize::MAX))
+    } else {
+        sim.get_state()
+    };
     Ok(warp::reply::json(&state))
 }
 
-async fn sarg04_reset_handler(
-    simulator: Arc<Mutex<SARG04Simulator>>,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut sim = simulator.lock().await;
-    sim.reset();
-    let state = sim.get_state();
+// Steps publish events with blocking sends, so they run on the blocking pool
+async fn run_step<F>(simulator: SharedProtocol, step: F) -> Result<impl warp::Reply, warp::Rejection>
+where
+    F: FnOnce(&mut dyn QkdProtocol) -> SimulationState + Send + 'static,
+{
+    let state = tokio::task::spawn_blocking(move || {
+        let mut sim = simulator.blocking_lock();
+        step(&mut *sim)
+    })
+    .await
+    .map_err(|_| warp::reject::custom(StepFailed))?;
     Ok(warp::reply::json(&state))
 }
 
-async fn sarg04_configure_hacker_handler(
-    config: HackerConfig,
-    simulator: Arc<Mutex<SARG04Simulator>>,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut sim = simulator.lock().await;
-    sim.configure_hacker(config);
-    let state = sim.get_state();
-    Ok(warp::reply::json(&state))
+// Turn runs above `MAX_RUN_PHOTONS` away before they reach the blocking pool
+fn check_photon_count(count: usize) -> Result<(), warp::Rejection> {
+    if count > MAX_RUN_PHOTONS {
+        return Err(warp::reject::custom(TooManyPhotons));
+    }
+    Ok(())
 }
 
-async fn sarg04_configure_noise_handler(
-    noise_model: qkd_simulator::simulator::NoiseModel,
-    simulator: Arc<Mutex<SARG04Simulator>>,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut sim = simulator.lock().await;
-    sim.configure_noise(noise_model);
-    let state = sim.get_state();
-    Ok(warp::reply::json(&state))
+// Sweeps run on the blocking pool and fan out over rayon
+async fn sweep_handler(request: SweepRequest) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = tokio::task::spawn_blocking(move || run_sweep(&request))
+        .await
+        .map_err(|_| warp::reject::custom(StepFailed))?
+        .map_err(|err| warp::reject::custom(SweepRejected(err)))?;
+    Ok(warp::reply::json(&result))
 }
 
-async fn sarg04_get_state_handler(
-    simulator: Arc<Mutex<SARG04Simulator>>,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    let sim = simulator.lock().await;
-    let state = sim.get_state();
-    Ok(warp::reply::json(&state))
+// Forward event batches to a WebSocket client until either side hangs up
+async fn stream_events(simulator: SharedProtocol, socket: WebSocket) {
+    let mut events = simulator.lock().await.subscribe();
+    let (mut outgoing, mut incoming) = socket.split();
+
+    loop {
+        tokio::select! {
+            batch = events.recv() => {
+                let Some(batch) = batch else { break };
+                let message = match serde_json::to_string(&*batch) {
+                    Ok(json) => Message::text(json),
+                    Err(_) => break,
+                };
+                // A client that stops reading is disconnected rather than
+                // left holding its queue
+                match tokio::time::timeout(SUBSCRIBER_SEND_TIMEOUT, outgoing.send(message)).await {
+                    Ok(Ok(())) => {}
+                    _ => break,
+                }
+            }
+            message = incoming.next() => {
+                match message {
+                    Some(Ok(message)) if !message.is_close() => {}
+                    _ => break,
+                }
+            }
+        }
+    }
 }
 
 async fn health_handler() -> Result<impl warp::Reply, warp::Rejection> {
@@ -333,15 +623,35 @@ async fn health_handler() -> Result<impl warp::Reply, warp::Rejection> {
     })))
 }
 
-// Helper functions to pass simulators to handlers
+// Turn session rejections into JSON errors; anything else keeps warp's default
+async fn handle_rejection(rejection: warp::Rejection) -> Result<impl warp::Reply, warp::Rejection> {
+    let (status, message) = if rejection.find::<SessionNotFound>().is_some() {
+        (StatusCode::NOT_FOUND, "session not found".to_string())
+    } else if let Some(SessionRejected(err)) = rejection.find() {
+        (StatusCode::SERVICE_UNAVAILABLE, err.to_string())
+    } else if let Some(SweepRejected(err)) = rejection.find() {
+        (StatusCode::BAD_REQUEST, err.to_string())
+    } else if rejection.find::<TooManyPhotons>().is_some() {
+        (StatusCode::BAD_REQUEST, format!("a run may generate at most {} photons", MAX_RUN_PHOTONS))
+    } else if rejection.find::<StepFailed>().is_some() {
+        (StatusCode::INTERNAL_SERVER_ERROR, "simulation step failed".to_string())
+    } else {
+        return Err(rejection);
+    };
+
+    let body = warp::reply::json(&serde_json::json!({ "error": message }));
+    Ok(warp::reply::with_status(body, status))
+}
+
+// Helper functions to pass shared state to handlers
 fn with_simulator(
-    simulator: Arc<Mutex<BB84Simulator>>,
-) -> impl Filter<Extract = (Arc<Mutex<BB84Simulator>>,), Error = std::convert::Infallible> + Clone {
+    simulator: SharedProtocol,
+) -> impl Filter<Extract = (SharedProtocol,), Error = std::convert::Infallible> + Clone {
     warp::any().map(move || simulator.clone())
 }
 
-fn with_sarg04_simulator(
-    simulator: Arc<Mutex<SARG04Simulator>>,
-) -> impl Filter<Extract = (Arc<Mutex<SARG04Simulator>>,), Error = std::convert::Infallible> + Clone {
-    warp::any().map(move || simulator.clone())
-}
\ No newline at end of file
+fn with_registry(
+    registry: Registry,
+) -> impl Filter<Extract = (Registry,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || registry.clone())
+}
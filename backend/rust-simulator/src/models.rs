@@ -1,3 +1,9 @@
+use crate::attack::{
+    InterceptResend, PartialIntercept, Breidbart, BeamSplitting, PhaseCovariantCloning, PhotonNumberSplitting,
+    SourceControl,
+};
+use crate::bits::BitVec;
+use crate::photons::PhotonColumns;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -5,51 +11,491 @@ pub struct QuantumBit {
     pub id: String,
     pub value: u8, // 0 or 1
     pub basis: Basis,
-    pub polarization: u16, // degrees (0, 45, 90, 135)
+    pub polarization: f64, // degrees from horizontal in [0, 180), or RIGHT_CIRCULAR / LEFT_CIRCULAR
     pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detection: Option<Detection>, // Bob's bits only
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+impl QuantumBit {
+    // Whether Bob registered anything; no-click positions never enter the key
+    pub fn is_detected(&self) -> bool {
+        self.detection != Some(Detection::NoClick)
+    }
+}
+
+// What Bob's pair of detectors registered for one time slot
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Detection {
+    Click,       // exactly one detector fired
+    NoClick,     // photon lost or missed, and no dark count
+    DoubleClick, // both fired; Bob assigns a random bit
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionStats {
+    pub clicks: usize,
+    pub no_clicks: usize,
+    pub double_clicks: usize,
+    pub detection_rate: f64, // (clicks + double clicks) / time slots
+}
+
+impl DetectionStats {
+    pub fn from_bits(bob_bits: &PhotonColumns) -> Self {
+        let count = |detection| (0..bob_bits.len()).filter(|&row| bob_bits.detection(row) == Some(detection)).count();
+        let clicks = count(Detection::Click);
+        let double_clicks = count(Detection::DoubleClick);
+        Self {
+            clicks,
+            no_clicks: count(Detection::NoClick),
+            double_clicks,
+            detection_rate: if bob_bits.is_empty() {
+                0.0
+            } else {
+                (clicks + double_clicks) as f64 / bob_bits.len() as f64
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Basis {
     Rectilinear, // +
     Diagonal,    // x
+    Circular,    // ○ right/left circular
+}
+
+impl Basis {
+    // Conjugate bases of BB84 and its relatives
+    pub const BB84: [Basis; 2] = [Basis::Rectilinear, Basis::Diagonal];
+    // All three mutually unbiased bases of the six-state protocol
+    pub const SIX_STATE: [Basis; 3] = [Basis::Rectilinear, Basis::Diagonal, Basis::Circular];
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimulationState {
-    pub alice_bits: Vec<QuantumBit>,
-    pub bob_bits: Vec<QuantumBit>,
-    pub shared_key: String,
-    pub intercepted_bits: Vec<QuantumBit>,
+    pub alice_bits: PhotonColumns, // serialized as arrays of `QuantumBit`
+    pub bob_bits: PhotonColumns,
+    pub shared_key: BitVec, // serialized as a string of '0' and '1', like every key
+    pub intercepted_bits: PhotonColumns,
     pub error_rate: f64,
     pub is_hacker_present: bool,
     pub phase: Phase,
     pub session_id: String,
     pub start_time: u64,
     pub end_time: u64,
+    #[serde(default)]
+    pub announced_pairs: Vec<AnnouncedPair>, // SARG04 only, one per position
+    #[serde(default)]
+    pub seed: u64, // replaying a run with this seed reproduces it exactly
+    #[serde(default)]
+    pub bob_key: BitVec, // Bob's sifted bits, differs from shared_key where errors hit
+    #[serde(default)]
+    pub parameter_estimation: Option<ParameterEstimationReport>,
+    #[serde(default)]
+    pub alice_corrected_key: BitVec,
+    #[serde(default)]
+    pub bob_corrected_key: BitVec,
+    #[serde(default)]
+    pub error_correction: Option<ErrorCorrectionReport>,
+    #[serde(default)]
+    pub secret_key: BitVec, // reconciled key after privacy amplification
+    #[serde(default)]
+    pub privacy_amplification: Option<PrivacyAmplificationReport>,
+    #[serde(default)]
+    pub abort_reason: Option<AbortReason>, // set when phase is Aborted
+    #[serde(default)]
+    pub detection_stats: Option<DetectionStats>,
+    #[serde(default)]
+    pub sifting: Option<SiftingStats>,
+    #[serde(default)]
+    pub expected_attack_qber: Option<f64>, // QBER Eve's attack should cause, set when present
+    #[serde(default)]
+    pub attack: Option<AttackReport>, // matching-basis protocols with Eve present
+    #[serde(default)]
+    pub information: Option<InformationReport>, // matching-basis protocols
+    #[serde(default)]
+    pub eve_key: BitVec, // simulator-only: Eve's guess of the sifted key, kept aligned with it
+    #[serde(default)]
+    pub key_rate: Option<KeyRateReport>, // set by `complete`
+    #[serde(default)]
+    pub analyzer_angles: Vec<[f64; 2]>, // E91 only, Alice's and Bob's analyzer angle per pair
+    #[serde(default)]
+    pub chsh: Option<ChshReport>, // E91 only
+    #[serde(default)]
+    pub pulses: Vec<Pulse>, // weak-coherent-pulse runs only, one per position
+    #[serde(default)]
+    pub decoy: Option<DecoyReport>,
+    #[serde(default)]
+    pub pns: Option<PnsReport>, // weak-coherent-pulse runs under a PNS attack
+    #[serde(default)]
+    pub window: Option<StateWindow>, // set when the per-position arrays hold only part of the run
+}
+
+impl SimulationState {
+    pub fn summary(&self) -> StateSummary {
+        StateSummary {
+            session_id: self.session_id.clone(),
+            phase: self.phase,
+            seed: self.seed,
+            positions: self.alice_bits.len(),
+            detections: self
+                .detection_stats
+                .as_ref()
+                .map_or(0, |stats| stats.clicks + stats.double_clicks),
+            intercepted: self.intercepted_bits.len(),
+            sifted_length: self.sifting.as_ref().map_or(0, |sifting| sifting.sifted),
+            secret_key_length: self.secret_key.len(),
+            error_rate: self.error_rate,
+            estimated_qber: self.parameter_estimation.as_ref().map(|estimate| estimate.estimated_qber),
+            is_hacker_present: self.is_hacker_present,
+            abort_reason: self.abort_reason.clone(),
+        }
+    }
+}
+
+// Positions covered by the per-position arrays of a windowed state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateWindow {
+    pub offset: usize,
+    pub limit: usize,     // positions covered, at most the limit asked for
+    pub positions: usize, // photons Alice sent in the whole run
+}
+
+// Counts, QBER and key length of a run, without any per-position data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSummary {
+    pub session_id: String,
+    pub phase: Phase,
+    pub seed: u64,
+    pub positions: usize,     // photons Alice sent
+    pub detections: usize,    // positions where Bob registered a click
+    pub intercepted: usize,   // positions Eve holds a record for
+    pub sifted_length: usize, // positions kept in the sifted key
+    pub secret_key_length: usize,
+    pub error_rate: f64,              // percent, over the whole sifted key
+    pub estimated_qber: Option<f64>,  // fraction, set once parameter estimation ran
+    pub is_hacker_present: bool,
+    pub abort_reason: Option<AbortReason>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Phase {
     Preparation,
     Transmission,
     Sifting,
     ErrorCheck,
+    ErrorCorrection,
+    PrivacyAmplification,
     Complete,
+    Aborted, // no secret key was produced, see `abort_reason`
+}
+
+// SARG04: Alice reveals two non-orthogonal states, one of which she sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnouncedPair {
+    pub index: usize,
+    pub states: [f64; 2], // polarizations in degrees, Alice's state is not marked
+    pub outcome: SiftOutcome,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SiftOutcome {
+    Conclusive,   // Bob's result excluded one state of the pair
+    Inconclusive, // Bob's result is compatible with both states
+}
+
+// QBER estimated from a publicly compared sample of the sifted key.
+// QBER values here are fractions, unlike the percentage in `error_rate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiftingStats {
+    pub positions: usize,     // photons Alice sent
+    pub detected: usize,      // positions where Bob (and Alice, for pair sources) registered a click
+    pub sifted: usize,        // positions kept in the sifted key
+    pub sifted_fraction: f64, // sifted / positions: ~50% BB84, ~25% SARG04 and B92
+}
+
+// Intensity class of a weak coherent pulse
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DecoyLevel {
+    Signal,
+    Decoy,
+    Vacuum,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pulse {
+    pub level: DecoyLevel,
+    pub photon_number: u32, // drawn from Poisson(intensity)
+}
+
+// Intensities and how often Alice picks each; vacuum takes the remainder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecoyConfig {
+    pub signal_intensity: f64,   // mean photon number μ
+    pub decoy_intensity: f64,    // mean photon number ν < μ
+    pub signal_probability: f64,
+    pub decoy_probability: f64,
+}
+
+impl Default for DecoyConfig {
+    fn default() -> Self {
+        Self {
+            signal_intensity: 0.5,
+            decoy_intensity: 0.1,
+            signal_probability: 0.8,
+            decoy_probability: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntensityStats {
+    pub level: DecoyLevel,
+    pub intensity: f64,
+    pub pulses: usize,
+    pub detections: usize,
+    pub gain: f64,   // Q: detections / pulses
+    pub sifted: usize,
+    pub errors: usize,
+    pub qber: f64,   // E: errors / sifted
+}
+
+// Vacuum + weak decoy estimate and the GLLP key rate it allows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyReport {
+    pub intensities: Vec<IntensityStats>, // signal, decoy, vacuum
+    pub background_yield: f64,     // Y0, from the vacuum gain
+    pub single_photon_yield: f64,  // lower bound on Y1
+    pub single_photon_gain: f64,   // lower bound on Q1 = μ e^(−μ) Y1
+    pub single_photon_error: f64,  // upper bound on e1
+    pub key_rate: f64,             // secret bits per signal pulse, floored at 0
+}
+
+// How well Eve's records match the sifted key where she attacked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttackReport {
+    pub attacked_bits: usize,  // sifted positions Eve has a record for
+    pub correct_guesses: usize, // of those, where her bit equals Alice's
+    pub guess_accuracy: f64,   // correct_guesses / attacked_bits
+    pub induced_qber: f64,     // QBER on the attacked positions
+}
+
+// Security proof a key rate is computed with
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyRateBound {
+    ShorPreskill, // BB84 and its entanglement-based versions
+    SixState,
+}
+
+// Concentration bound used for the phase error of the unsampled bits
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FluctuationBound {
+    Serfling,
+    Hoeffding,
+}
+
+// Secret key rate of a run, from its estimated QBER and block sizes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRateReport {
+    pub bound: KeyRateBound,
+    pub qber: f64, // estimated QBER the rates are evaluated at
+    pub asymptotic_rate: f64, // secret bits per sifted bit as the block grows
+    pub asymptotic_rate_per_pulse: f64, // times the sifted fraction
+    pub finite_key: Vec<FiniteKeyReport>, // one per fluctuation bound, after parameter estimation
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiniteKeyReport {
+    pub fluctuation_bound: FluctuationBound,
+    pub block_size: usize, // n, key bits left after the sample
+    pub sample_size: usize, // k
+    pub phase_error_deviation: f64, // μ
+    pub phase_error_bound: f64, // Q + μ
+    pub leaked_bits: usize, // error-correction leakage, estimated before Cascade runs
+    pub epsilon_pe: f64,
+    pub epsilon_pa: f64,
+    pub epsilon_cor: f64,
+    pub key_length: usize, // secure length ℓ
+    pub rate: f64, // ℓ / n
+}
+
+// What Alice, Bob and Eve share on the sifted key, in bits per sifted bit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InformationReport {
+    pub alice_bob: f64, // I(A:B)
+    pub alice_eve: f64, // I(A:E)
+    pub bob_eve: f64,   // I(B:E)
+    pub csiszar_korner: bool, // I(A:B) > I(A:E): one-way post-processing can beat Eve
+    pub eve_final_key_error_rate: Option<f64>, // Eve's hashed guess against the secret key
+}
+
+// What a photon-number-splitting Eve got away with on the signal pulses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PnsReport {
+    pub split_pulses: usize,   // multi-photon pulses Eve kept a photon from
+    pub sifted_bits: usize,
+    pub known_bits: usize,     // sifted bits Eve read off her stored photon
+    pub information_gain: f64, // known_bits / sifted_bits
+    pub qber: f64,             // sifted signal QBER, unchanged by the attack
+}
+
+// CHSH test over the E91 pairs measured with non-matching analyzers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChshReport {
+    pub s_value: f64,           // 2√2 for ideal Bell pairs, at most 2 classically
+    pub uncertainty: f64,       // one standard deviation of S
+    pub correlations: [f64; 4], // E(a1,b1), E(a1,b3), E(a3,b1), E(a3,b3)
+    pub samples: [usize; 4],    // detected pairs behind each correlation
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterEstimationReport {
+    pub sample_fraction: f64,
+    pub sample_size: usize,
+    pub sample_errors: usize,
+    pub estimated_qber: f64,
+    pub confidence_interval: [f64; 2],
+    pub confidence_level: f64,
+    pub true_qber: f64, // simulator-only: errors over the whole sifted key
+    pub remaining_key_length: usize, // sampled bits are removed from the key
+}
+
+// Outcome of Cascade reconciliation between Alice's and Bob's sifted keys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorCorrectionReport {
+    pub passes: usize,
+    pub initial_block_size: usize,
+    pub disclosed_parities: usize, // bits leaked to Eve
+    pub corrected_errors: usize,
+    pub residual_errors: usize,
+    pub efficiency: Option<f64>, // f_EC = leaked / (n * h(QBER)), None when error free
+}
+
+// Toeplitz hashing of the reconciled key down to its secure length
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyAmplificationReport {
+    pub input_length: usize,
+    pub output_length: usize,
+    pub hash_seed: u64, // public seed of the Toeplitz matrix
+    pub leaked_bits: usize, // error-correction leakage subtracted from the key
+    pub security_parameter: f64, // ε_PA
+    pub keys_match: bool, // Alice's and Bob's hashed keys agree
+}
+
+// Why a run ended without a key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AbortReason {
+    QberAboveThreshold { estimated_qber: f64, threshold: f64 },
+    NoSecretKey { input_length: usize }, // privacy amplification left nothing
+    EmptySample { sample_fraction: f64 }, // no sifted bits were compared, the QBER is unknown
+    KeysDiffer { residual_errors: usize }, // error correction left Alice's and Bob's keys different
 }
 
+/// Eve's strategy, tagged by `attack`, e.g.
+/// `{"attack": "breidbart", "interception_rate": 1.0}`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HackerConfig {
-    pub interception_rate: f64,    // 0.0 to 1.0
-    pub measurement_error_rate: f64, // 0.0 to 1.0
-    pub resend_error_rate: f64,      // 0.0 to 1.0
+#[serde(tag = "attack", rename_all = "snake_case")]
+pub enum HackerConfig {
+    InterceptResend(InterceptResend),
+    PartialIntercept(PartialIntercept),
+    Breidbart(Breidbart),
+    BeamSplitting(BeamSplitting),
+    PhaseCovariantCloning(PhaseCovariantCloning),
+    PhotonNumberSplitting(PhotonNumberSplitting),
+    SourceControl(SourceControl),
+}
+
+impl Default for HackerConfig {
+    fn default() -> Self {
+        HackerConfig::PartialIntercept(PartialIntercept {
+            interception_rate: 0.5,
+            measurement_error_rate: 0.1,
+            resend_error_rate: 0.1,
+        })
+    }
+}
+
+// Where an entanglement-based protocol's pair source sits on the link
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SourcePosition {
+    Alice,  // Alice's photon never enters the channel
+    #[default]
+    Midway, // each photon crosses half of the channel
+    Bob,    // Bob's photon never enters the channel
 }
 
 // Advanced noise models
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NoiseModel {
     pub detector_efficiency: f64,     // 0.0 to 1.0
-    pub dark_count_rate: f64,         // Probability of dark counts
-    pub polarization_drift: f64,      // Polarization drift over time
+    pub dark_count_rate: f64,         // Dark count probability per detector and time slot
+    pub polarization_drift: f64,      // Rotation in degrees per photon slot
     pub loss_probability: f64,        // Photon loss probability
+    pub analyzer_misalignment: f64,   // Rotation of Bob's analyzer in degrees
+}
+
+// An ideal channel: every photon arrives and is detected
+impl Default for NoiseModel {
+    fn default() -> Self {
+        Self {
+            detector_efficiency: 1.0,
+            dark_count_rate: 0.0,
+            polarization_drift: 0.0,
+            loss_probability: 0.0,
+            analyzer_misalignment: 0.0,
+        }
+    }
+}
+
+// A fiber link to Bob's gated detectors, described in link-budget terms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FiberChannel {
+    pub length_km: f64,
+    pub attenuation_db_per_km: f64,  // 0.2 for telecom fiber at 1550 nm
+    pub connector_loss_db: f64,      // connectors, splices and Bob's optics in total
+    pub detector_efficiency: f64,    // 0.0 to 1.0
+    pub dark_count_probability: f64, // per detector and gate
+}
+
+// Telecom fiber into InGaAs avalanche photodiodes
+impl Default for FiberChannel {
+    fn default() -> Self {
+        Self {
+            length_km: 0.0,
+            attenuation_db_per_km: 0.2,
+            connector_loss_db: 0.0,
+            detector_efficiency: 0.1,
+            dark_count_probability: 1e-6,
+        }
+    }
+}
+
+impl FiberChannel {
+    pub fn loss_db(&self) -> f64 {
+        self.length_km * self.attenuation_db_per_km + self.connector_loss_db
+    }
+
+    // Probability that a photon reaches Bob's detectors
+    pub fn transmittance(&self) -> f64 {
+        10f64.powf(-self.loss_db() / 10.0)
+    }
+
+    // Noise model for this link, keeping `base`'s drift and misalignment
+    pub fn noise_model(&self, base: &NoiseModel) -> NoiseModel {
+        NoiseModel {
+            detector_efficiency: self.detector_efficiency,
+            dark_count_rate: self.dark_count_probability,
+            loss_probability: 1.0 - self.transmittance(),
+            ..base.clone()
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,302 @@
+use crate::bits::BitVec;
+use crate::models::{QuantumBit, Basis, Detection};
+use crate::protocol::polarization_for;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::ops::Range;
+
+// Time between two of Alice's emissions
+pub const SLOT_MILLIS: u64 = 100;
+
+/// One photon as the simulators handle it. Its id and timestamp follow from
+/// its position in the run and are only filled in for the API.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Photon {
+    pub basis: Basis,
+    pub value: u8, // 0 or 1
+    pub polarization: f64, // degrees, as in `QuantumBit`
+    pub detection: Option<Detection>, // Bob's bits only
+}
+
+impl Photon {
+    // The state `value` is encoded as in `basis`, undisturbed
+    pub fn prepared(basis: Basis, value: u8) -> Self {
+        Self {
+            basis,
+            value,
+            polarization: polarization_for(&basis, value),
+            detection: None,
+        }
+    }
+
+    // Whether Bob registered anything; no-click positions never enter the key
+    pub fn is_detected(&self) -> bool {
+        self.detection != Some(Detection::NoClick)
+    }
+}
+
+/// Whose records a set of columns holds, which names and times them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Party {
+    #[default]
+    Alice,
+    Bob,
+    Eve,
+}
+
+impl Party {
+    fn prefix(self) -> &'static str {
+        match self {
+            Party::Alice => "alice",
+            Party::Bob => "bob",
+            Party::Eve => "hacker",
+        }
+    }
+
+    // Bob's detection comes half a slot after Alice's emission
+    fn delay(self) -> u64 {
+        match self {
+            Party::Bob => SLOT_MILLIS / 2,
+            _ => 0,
+        }
+    }
+}
+
+/// One party's photon records stored column by column.
+///
+/// Values take one bit per photon, bases two and detections a pair of
+/// masks. Polarizations are only stored once some photon's differs from the
+/// nominal angle of its basis and value. Ids and timestamps are derived from
+/// each record's position in the run; Eve's records, which cover only some
+/// positions, keep those positions in a column of their own.
+#[derive(Debug, Clone, Default)]
+pub struct PhotonColumns {
+    party: Party,
+    epoch: u64, // timestamp of position 0
+    values: BitVec,
+    bases: BitVec, // two bits per photon, see `basis_code`
+    measured: bool, // whether the records carry detections
+    detected: BitVec, // Click or DoubleClick
+    double_clicks: BitVec,
+    polarizations: Option<Vec<f64>>,
+    positions: Option<Vec<usize>>, // sparse columns only
+}
+
+impl PhotonColumns {
+    // Records for every position from 0 on
+    pub fn dense(party: Party, epoch: u64) -> Self {
+        Self {
+            party,
+            epoch,
+            ..Self::default()
+        }
+    }
+
+    // Records for some positions only, added with `push_at`
+    pub fn sparse(party: Party, epoch: u64) -> Self {
+        Self {
+            positions: Some(Vec::new()),
+            ..Self::dense(party, epoch)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // Append the record for the next position
+    pub fn push(&mut self, photon: Photon) {
+        assert!(self.positions.is_none(), "sparse columns need a position");
+        self.append(photon);
+    }
+
+    // Append the record for `position`
+    pub fn push_at(&mut self, position: usize, photon: Photon) {
+        match &mut self.positions {
+            Some(positions) => positions.push(position),
+            None => panic!("dense columns are filled in position order"),
+        }
+        self.append(photon);
+    }
+
+    fn append(&mut self, photon: Photon) {
+        if self.is_empty() {
+            self.measured = photon.detection.is_some();
+        }
+        let row = self.len();
+        let code = basis_code(photon.basis);
+        self.values.push(photon.value);
+        self.bases.push(code & 1);
+        self.bases.push(code >> 1);
+        if self.measured {
+            let detection = photon.detection.unwrap_or(Detection::NoClick);
+            self.detected.push(u8::from(detection != Detection::NoClick));
+            self.double_clicks.push(u8::from(detection == Detection::DoubleClick));
+        }
+
+        let nominal = polarization_for(&photon.basis, photon.value);
+        match &mut self.polarizations {
+            Some(polarizations) => polarizations.push(photon.polarization),
+            None if photon.polarization != nominal => {
+                let mut polarizations: Vec<f64> = (0..row).map(|row| self.nominal_polarization(row)).collect();
+                polarizations.push(photon.polarization);
+                self.polarizations = Some(polarizations);
+            }
+            None => {}
+        }
+    }
+
+    pub fn get(&self, row: usize) -> Photon {
+        Photon {
+            basis: self.basis(row),
+            value: self.value(row),
+            polarization: match &self.polarizations {
+                Some(polarizations) => polarizations[row],
+                None => self.nominal_polarization(row),
+            },
+            detection: self.detection(row),
+        }
+    }
+
+    pub fn value(&self, row: usize) -> u8 {
+        self.values.get(row)
+    }
+
+    pub fn basis(&self, row: usize) -> Basis {
+        basis_from_code(self.bases.get(2 * row) | (self.bases.get(2 * row + 1) << 1))
+    }
+
+    pub fn detection(&self, row: usize) -> Option<Detection> {
+        self.measured.then(|| match (self.detected.get(row), self.double_clicks.get(row)) {
+            (0, _) => Detection::NoClick,
+            (_, 0) => Detection::Click,
+            _ => Detection::DoubleClick,
+        })
+    }
+
+    pub fn is_detected(&self, row: usize) -> bool {
+        !self.measured || self.detected.get(row) == 1
+    }
+
+    // Position in the run the record at `row` belongs to
+    pub fn position(&self, row: usize) -> usize {
+        self.positions.as_ref().map_or(row, |positions| positions[row])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Photon> + '_ {
+        (0..self.len()).map(|row| self.get(row))
+    }
+
+    // Every record with the position it belongs to
+    pub fn iter_positions(&self) -> impl Iterator<Item = (usize, Photon)> + '_ {
+        (0..self.len()).map(|row| (self.position(row), self.get(row)))
+    }
+
+    /// The record at `row` in its API form.
+    pub fn quantum_bit(&self, row: usize) -> QuantumBit {
+        let photon = self.get(row);
+        let position = self.position(row);
+        QuantumBit {
+            id: format!("{}-{}", self.party.prefix(), position),
+            value: photon.value,
+            basis: photon.basis,
+            polarization: photon.polarization,
+            timestamp: self.epoch + position as u64 * SLOT_MILLIS + self.party.delay(),
+            detection: photon.detection,
+        }
+    }
+
+    pub fn quantum_bits(&self) -> impl Iterator<Item = QuantumBit> + '_ {
+        (0..self.len()).map(|row| self.quantum_bit(row))
+    }
+
+    /// The records for the positions in `range`, keeping their ids and timestamps.
+    pub fn window(&self, range: Range<usize>) -> PhotonColumns {
+        // Sparse records are pushed in position order
+        let rows = match &self.positions {
+            Some(positions) => {
+                positions.partition_point(|&position| position < range.start)
+                    ..positions.partition_point(|&position| position < range.end)
+            }
+            None => range.start.min(self.len())..range.end.min(self.len()),
+        };
+        let mut window = Self::sparse(self.party, self.epoch);
+        for row in rows {
+            window.push_at(self.position(row), self.get(row));
+        }
+        window
+    }
+
+    fn nominal_polarization(&self, row: usize) -> f64 {
+        polarization_for(&self.basis(row), self.value(row))
+    }
+}
+
+// Bases in two bits: rectilinear 0, diagonal 1, circular 2
+fn basis_code(basis: Basis) -> u8 {
+    match basis {
+        Basis::Rectilinear => 0,
+        Basis::Diagonal => 1,
+        Basis::Circular => 2,
+    }
+}
+
+fn basis_from_code(code: u8) -> Basis {
+    match code {
+        0 => Basis::Rectilinear,
+        1 => Basis::Diagonal,
+        _ => Basis::Circular,
+    }
+}
+
+// The API sees the columns as a plain array of `QuantumBit`s, expanded as
+// they are written out
+impl Serialize for PhotonColumns {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.quantum_bits())
+    }
+}
+
+impl<'de> Deserialize<'de> for PhotonColumns {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bits = Vec::<QuantumBit>::deserialize(deserializer)?;
+        let Some(first) = bits.first() else {
+            return Ok(Self::default());
+        };
+
+        let mut positions = Vec::with_capacity(bits.len());
+        let mut party = Party::Alice;
+        for bit in &bits {
+            let (prefix, position) = bit
+                .id
+                .rsplit_once('-')
+                .and_then(|(prefix, position)| Some((prefix, position.parse::<usize>().ok()?)))
+                .ok_or_else(|| de::Error::custom(format!("malformed photon id {:?}", bit.id)))?;
+            party = match prefix {
+                "alice" => Party::Alice,
+                "bob" => Party::Bob,
+                "hacker" => Party::Eve,
+                _ => return Err(de::Error::custom(format!("unknown party in photon id {:?}", bit.id))),
+            };
+            positions.push(position);
+        }
+
+        let epoch = first.timestamp.saturating_sub(positions[0] as u64 * SLOT_MILLIS + party.delay());
+        let dense = positions.iter().enumerate().all(|(row, &position)| row == position);
+        let mut columns = if dense { Self::dense(party, epoch) } else { Self::sparse(party, epoch) };
+        for (bit, position) in bits.into_iter().zip(positions) {
+            let photon = Photon {
+                basis: bit.basis,
+                value: bit.value,
+                polarization: bit.polarization,
+                detection: bit.detection,
+            };
+            if dense { columns.push(photon) } else { columns.push_at(position, photon) }
+        }
+        Ok(columns)
+    }
+}
@@ -0,0 +1,106 @@
+use crate::models::Basis;
+use crate::protocol::{LEFT_CIRCULAR, RIGHT_CIRCULAR};
+use crate::rng::PhotonRng;
+
+/// Polarization of a photon as a Stokes vector on the Poincaré sphere.
+///
+/// Pure states have unit length; a shorter vector is a mixed state, as left
+/// behind by a cloning machine. Horizontal is +s1, 45° is +s2 and right
+/// circular is +s3.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Polarization {
+    pub s1: f64,
+    pub s2: f64,
+    pub s3: f64,
+}
+
+impl Polarization {
+    // The state encoding `value` in `basis`
+    pub fn of(basis: Basis, value: u8) -> Self {
+        let sign = if value == 0 { 1.0 } else { -1.0 };
+        match basis {
+            Basis::Rectilinear => Self { s1: sign, s2: 0.0, s3: 0.0 },
+            Basis::Diagonal => Self { s1: 0.0, s2: sign, s3: 0.0 },
+            Basis::Circular => Self { s1: 0.0, s2: 0.0, s3: sign },
+        }
+    }
+
+    // Linear polarization at `degrees` from horizontal
+    pub fn linear(degrees: f64) -> Self {
+        let doubled = (2.0 * degrees).to_radians();
+        Self { s1: doubled.cos(), s2: doubled.sin(), s3: 0.0 }
+    }
+
+    // The state a `QuantumBit::polarization` angle stands for
+    pub fn from_degrees(degrees: f64) -> Self {
+        if degrees == RIGHT_CIRCULAR {
+            Self { s1: 0.0, s2: 0.0, s3: 1.0 }
+        } else if degrees == LEFT_CIRCULAR {
+            Self { s1: 0.0, s2: 0.0, s3: -1.0 }
+        } else {
+            Self::linear(degrees)
+        }
+    }
+
+    // Physically rotate the polarization by `degrees`; circular states are unchanged
+    pub fn rotated(self, degrees: f64) -> Self {
+        let (sin, cos) = (2.0 * degrees).to_radians().sin_cos();
+        Self { s1: self.s1 * cos - self.s2 * sin, s2: self.s1 * sin + self.s2 * cos, s3: self.s3 }
+    }
+
+    pub fn scaled(self, factor: f64) -> Self {
+        Self { s1: self.s1 * factor, s2: self.s2 * factor, s3: self.s3 * factor }
+    }
+
+    /// Measure along `axis`: result 0 with probability (1 + s·n) / 2, which
+    /// for linear states is Malus's law cos²(θ_photon − θ_analyzer).
+    pub fn measure_along(self, axis: Polarization, rng: &mut PhotonRng) -> u8 {
+        if rng.chance((1.0 + self.overlap(axis)) / 2.0) { 0 } else { 1 }
+    }
+
+    // Scalar product of the Stokes vectors, cos of the angle between them
+    pub fn overlap(self, other: Polarization) -> f64 {
+        self.s1 * other.s1 + self.s2 * other.s2 + self.s3 * other.s3
+    }
+
+    /// Analyzer that best tells `self` from `other`: the unit vector along
+    /// their difference, with result 0 pointing to `self` (Helstrom). For
+    /// orthogonal states it is `self` itself.
+    pub fn axis_against(self, other: Polarization) -> Self {
+        let difference = Self { s1: self.s1 - other.s1, s2: self.s2 - other.s2, s3: self.s3 - other.s3 };
+        difference.scaled(1.0 / difference.overlap(difference).sqrt())
+    }
+
+    // Index of the one of `states` this state lies nearer, None when it is
+    // as near to both
+    pub fn nearest(self, states: [Polarization; 2]) -> Option<usize> {
+        let [first, second] = states.map(|state| self.overlap(state));
+        if (first - second).abs() < 1e-9 {
+            None
+        } else {
+            Some(usize::from(second > first))
+        }
+    }
+
+    pub fn measure(self, basis: Basis, rng: &mut PhotonRng) -> u8 {
+        self.measure_along(Self::of(basis, 0), rng)
+    }
+
+    // Angle for `QuantumBit::polarization`, with the circular sentinels for
+    // mostly circular states
+    pub fn degrees(self) -> f64 {
+        let linear = self.s1.hypot(self.s2);
+        if self.s3.abs() > linear {
+            if self.s3 > 0.0 { RIGHT_CIRCULAR } else { LEFT_CIRCULAR }
+        } else {
+            wrap_degrees(self.s2.atan2(self.s1).to_degrees() / 2.0)
+        }
+    }
+}
+
+// Bring a linear polarization angle into [0, 180)
+pub fn wrap_degrees(degrees: f64) -> f64 {
+    let wrapped = degrees.rem_euclid(180.0);
+    // rem_euclid rounds tiny negative angles up to 180, the circular sentinel
+    if wrapped >= 180.0 { 0.0 } else { wrapped }
+}
@@ -0,0 +1,168 @@
+use crate::bits::BitVec;
+use crate::models::PrivacyAmplificationReport;
+use crate::protocol::PARALLEL_THRESHOLD;
+use crate::rng::{PhotonRng, Stream};
+use crate::stats::binary_entropy;
+use rand::RngCore;
+use rayon::prelude::*;
+
+// Default ε_PA: the final key is ε-close to uniform from Eve's point of view
+pub const DEFAULT_SECURITY_PARAMETER: f64 = 1e-10;
+
+/// Secure key length ℓ = n·(1 − h(Q)) − leak_EC − 2·log2(1/ε), floored at 0.
+///
+/// Q is capped at 0.5, where Eve may know everything; past it h(Q) would
+/// fall again and credit a noisier key with more secrecy.
+pub fn secure_length(input_length: usize, qber: f64, leaked_bits: usize, epsilon: f64) -> usize {
+    let n = input_length as f64;
+    let length = n * (1.0 - binary_entropy(qber.clamp(0.0, 0.5))) - leaked_bits as f64 - 2.0 * (1.0 / epsilon).log2();
+    if length > 0.0 { length.floor() as usize } else { 0 }
+}
+
+// NTT prime 15·2^27 + 1 with primitive root 31. Hash convolutions count at
+// most n < 2^27 ones per point, so they come out exact
+const NTT_MODULUS: u64 = 2_013_265_921;
+const NTT_ROOT: u64 = 31;
+
+// Butterfly groups at least this wide are split across the rayon pool
+const PARALLEL_BUTTERFLIES: usize = 1 << 12;
+
+/// Compress `key` to `output_length` bits with a random Toeplitz matrix.
+///
+/// The m×n matrix is fixed by n + m − 1 public random bits expanded from
+/// `hash_seed`; Alice and Bob apply the same matrix to their keys. Row i is
+/// the diagonal window starting at m − 1 − i, so the product is a
+/// convolution of the diagonal with the reversed key, taken mod 2. It is
+/// computed with a number-theoretic transform in O((n + m)·log(n + m)).
+pub fn toeplitz_hash(key: &BitVec, output_length: usize, hash_seed: u64) -> BitVec {
+    let n = key.len();
+    if n == 0 || output_length == 0 {
+        return BitVec::new();
+    }
+
+    // Diagonal bits, packed little-endian
+    let diagonal_len = n + output_length - 1;
+    let mut rng = PhotonRng::new(hash_seed, Stream::PrivacyAmplification, 0);
+    let diagonal: Vec<u64> = (0..diagonal_len.div_ceil(64) + 1).map(|_| rng.next_u64()).collect();
+
+    // A cyclic convolution of this size only wraps into points below n − 1,
+    // which no row reads
+    let size = diagonal_len.next_power_of_two();
+    assert!(size <= 1 << 27, "key of {} bits is too long to hash", n);
+    let mut reversed_key: Vec<u32> = vec![0; size];
+    for (position, slot) in reversed_key[..n].iter_mut().enumerate() {
+        *slot = u32::from(key.get(n - 1 - position));
+    }
+    let mut diagonal_bits: Vec<u32> = (0..size)
+        .map(|bit| if bit < diagonal_len { ((diagonal[bit / 64] >> (bit % 64)) & 1) as u32 } else { 0 })
+        .collect();
+
+    ntt(&mut reversed_key, false);
+    ntt(&mut diagonal_bits, false);
+    reversed_key.par_iter_mut().zip(&diagonal_bits).for_each(|(a, &b)| *a = mul(*a, b));
+    drop(diagonal_bits);
+    ntt(&mut reversed_key, true);
+
+    (0..output_length)
+        .map(|row| (reversed_key[n - 1 + output_length - 1 - row] & 1) as u8)
+        .collect()
+}
+
+fn mul(a: u32, b: u32) -> u32 {
+    (a as u64 * b as u64 % NTT_MODULUS) as u32
+}
+
+// `value` below 2·`NTT_MODULUS` brought into range
+fn reduce(value: u64) -> u32 {
+    (if value >= NTT_MODULUS { value - NTT_MODULUS } else { value }) as u32
+}
+
+fn pow(mut base: u64, mut exponent: u64) -> u64 {
+    let mut result = 1;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % NTT_MODULUS;
+        }
+        base = base * base % NTT_MODULUS;
+        exponent >>= 1;
+    }
+    result
+}
+
+// In-place iterative NTT over `NTT_MODULUS`; `values.len()` is a power of two
+fn ntt(values: &mut [u32], inverse: bool) {
+    let size = values.len();
+    if size == 1 {
+        return;
+    }
+    let bits = size.trailing_zeros();
+    for i in 0..size {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= size {
+        let half = len / 2;
+        let mut step = pow(NTT_ROOT, (NTT_MODULUS - 1) / len as u64);
+        if inverse {
+            step = pow(step, NTT_MODULUS - 2);
+        }
+        let twiddles: Vec<u32> = std::iter::successors(Some(1u32), |&w| Some(mul(w, step as u32)))
+            .take(half)
+            .collect();
+        let butterfly = |(low, high): (&mut u32, &mut u32), &twiddle: &u32| {
+            let (u, v) = (*low as u64, mul(*high, twiddle) as u64);
+            *low = reduce(u + v);
+            *high = reduce(u + NTT_MODULUS - v);
+        };
+
+        // Use parallel processing for large transforms
+        if size <= PARALLEL_THRESHOLD {
+            for group in values.chunks_mut(len) {
+                let (low, high) = group.split_at_mut(half);
+                low.iter_mut().zip(high.iter_mut()).zip(&twiddles).for_each(|(pair, twiddle)| butterfly(pair, twiddle));
+            }
+        } else if half >= PARALLEL_BUTTERFLIES {
+            for group in values.chunks_mut(len) {
+                let (low, high) = group.split_at_mut(half);
+                low.par_iter_mut().zip(high.par_iter_mut()).zip(twiddles.par_iter()).for_each(|(pair, twiddle)| butterfly(pair, twiddle));
+            }
+        } else {
+            values.par_chunks_mut(len).for_each(|group| {
+                let (low, high) = group.split_at_mut(half);
+                low.iter_mut().zip(high.iter_mut()).zip(&twiddles).for_each(|(pair, twiddle)| butterfly(pair, twiddle));
+            });
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        let scale = pow(size as u64, NTT_MODULUS - 2) as u32;
+        values.par_iter_mut().for_each(|value| *value = mul(*value, scale));
+    }
+}
+
+pub fn amplify(
+    alice_key: &BitVec,
+    bob_key: &BitVec,
+    output_length: usize,
+    leaked_bits: usize,
+    epsilon: f64,
+    hash_seed: u64,
+) -> (BitVec, PrivacyAmplificationReport) {
+    let alice_secret = toeplitz_hash(alice_key, output_length, hash_seed);
+    let bob_secret = toeplitz_hash(bob_key, output_length, hash_seed);
+
+    let report = PrivacyAmplificationReport {
+        input_length: alice_key.len(),
+        output_length,
+        hash_seed,
+        leaked_bits,
+        security_parameter: epsilon,
+        keys_match: alice_secret == bob_secret,
+    };
+    (alice_secret, report)
+}
@@ -0,0 +1,783 @@
+use crate::models::{
+    SimulationState, HackerConfig, Basis, Phase, NoiseModel, FiberChannel, SourcePosition, DecoyConfig, Detection, DetectionStats, SiftingStats,
+    ErrorCorrectionReport, PrivacyAmplificationReport, ParameterEstimationReport, AbortReason, KeyRateBound,
+    StateSummary, StateWindow,
+};
+use crate::attack::{Announcement, InFlight, transmit, detect, attack_report};
+use crate::bits::BitVec;
+use crate::photons::{Photon, PhotonColumns, Party};
+use crate::information::{eve_view, information_report, eve_guess};
+use crate::polarization::{Polarization, wrap_degrees};
+use crate::rng::{PhotonRng, Stream};
+use crate::events::{EventSink, EventBatch, SimulationEvent, EVENT_BATCH_SIZE};
+use crate::cascade::run_cascade;
+use crate::privacy::{self, DEFAULT_SECURITY_PARAMETER};
+use crate::estimation::{estimate_qber, DEFAULT_SAMPLE_FRACTION};
+use crate::key_rate::{key_rate_report, finite_key_length};
+use rand::RngCore;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+use rayon::prelude::*;
+
+// Above this many photons the per-bit work is spread across the rayon pool
+pub const PARALLEL_THRESHOLD: usize = 1000;
+
+// Photons are worked on this many at a time, so only one chunk is ever held
+// outside the packed columns
+pub const UNPACKED_CHUNK: usize = 1 << 16;
+
+// Shor–Preskill limit for BB84 with one-way post-processing
+pub const BB84_QBER_THRESHOLD: f64 = 0.11;
+
+/// Lifecycle shared by every QKD protocol simulator.
+///
+/// Implementors only have to hand out their `ProtocolCore`; the default
+/// methods run the standard prepare-and-measure flow on it. Protocols that
+/// differ in a single step (e.g. SARG04 sifting) override just that step.
+pub trait QkdProtocol: Send {
+    /// Short protocol identifier, also used as the HTTP route prefix
+    fn name(&self) -> &'static str;
+
+    fn core(&self) -> &ProtocolCore;
+
+    fn core_mut(&mut self) -> &mut ProtocolCore;
+
+    // Generate random quantum bits for Alice
+    fn generate_alice_bits(&mut self, count: usize) -> &PhotonColumns {
+        self.core_mut().generate_alice_bits(count)
+    }
+
+    // Bob measures the quantum bits with random bases
+    fn measure_bits(&mut self, hacker_present: bool) -> &PhotonColumns {
+        self.core_mut().measure_bits(hacker_present)
+    }
+
+    // Sift the key by comparing bases
+    fn sift_key(&mut self) -> &BitVec {
+        self.core_mut().sift_matching_bases()
+    }
+
+    // Sacrifice a random sample of the sifted key to estimate the QBER
+    fn estimate_parameters(&mut self, sample_fraction: f64) -> ParameterEstimationReport {
+        self.core_mut().estimate_parameters(sample_fraction)
+    }
+
+    // Reconcile Alice's and Bob's sifted keys with Cascade
+    fn correct_errors(&mut self) -> ErrorCorrectionReport {
+        self.core_mut().correct_errors()
+    }
+
+    // Hash the reconciled key down to a secret key; `epsilon` is ε_PA
+    fn amplify_privacy(&mut self, epsilon: f64) -> PrivacyAmplificationReport {
+        let bound = self.key_rate_bound();
+        self.core_mut().amplify_privacy(bound, epsilon)
+    }
+
+    // Complete the simulation, running any post-processing that was skipped
+    // Ends in `Aborted` instead when no bits were sampled to estimate the
+    // QBER, the estimate is above the threshold, no secret key survives
+    // privacy amplification or Alice's and Bob's final keys differ.
+    fn complete_simulation(&mut self) -> SimulationState {
+        let sifted = self.core().state.phase == Phase::ErrorCheck;
+        if sifted && self.core().state.parameter_estimation.is_none() {
+            self.estimate_parameters(DEFAULT_SAMPLE_FRACTION);
+        }
+
+        // An empty sample says nothing about Eve
+        let empty_sample = self.core().state.parameter_estimation.as_ref().filter(|estimate| estimate.sample_size == 0);
+        if let Some(estimate) = empty_sample {
+            let sample_fraction = estimate.sample_fraction;
+            record_key_rate(self);
+            return self.core_mut().abort(AbortReason::EmptySample { sample_fraction });
+        }
+
+        let estimated_qber = self.core().estimated_qber();
+        let threshold = self.qber_threshold();
+        if estimated_qber > threshold {
+            record_key_rate(self);
+            return self.core_mut().abort(AbortReason::QberAboveThreshold { estimated_qber, threshold });
+        }
+
+        if sifted {
+            self.correct_errors();
+        }
+        if self.core().state.phase == Phase::ErrorCorrection {
+            self.amplify_privacy(DEFAULT_SECURITY_PARAMETER);
+        }
+        record_key_rate(self);
+        if self.core().state.secret_key.is_empty() {
+            let input_length = self.core().state.alice_corrected_key.len();
+            return self.core_mut().abort(AbortReason::NoSecretKey { input_length });
+        }
+        let keys_match = self.core().state.privacy_amplification.as_ref().is_none_or(|report| report.keys_match);
+        if !keys_match {
+            let residual_errors = self.core().state.error_correction.as_ref().map_or(0, |report| report.residual_errors);
+            return self.core_mut().abort(AbortReason::KeysDiffer { residual_errors });
+        }
+        self.core_mut().complete()
+    }
+
+    // Highest estimated QBER (fraction) this protocol can still distil a key from
+    fn default_qber_threshold(&self) -> f64 {
+        BB84_QBER_THRESHOLD
+    }
+
+    // Proof behind the reported key rate; None when no single-photon bound applies
+    fn key_rate_bound(&self) -> Option<KeyRateBound> {
+        Some(KeyRateBound::ShorPreskill)
+    }
+
+    fn qber_threshold(&self) -> f64 {
+        self.core().qber_threshold.unwrap_or_else(|| self.default_qber_threshold())
+    }
+
+    // Override the abort threshold; `None` restores the protocol default
+    fn set_qber_threshold(&mut self, threshold: Option<f64>) {
+        self.core_mut().qber_threshold = threshold;
+    }
+
+    // Reset simulation
+    fn reset(&mut self) {
+        self.core_mut().reset();
+    }
+
+    // Configure hacker parameters
+    fn configure_hacker(&mut self, config: HackerConfig) {
+        self.core_mut().hacker_config = config;
+    }
+
+    // Configure noise model
+    fn configure_noise(&mut self, noise_model: NoiseModel) {
+        self.core_mut().noise_model = noise_model;
+    }
+
+    // Derive the loss, detector efficiency and dark counts from a fiber link
+    fn configure_channel(&mut self, channel: FiberChannel) {
+        let noise_model = channel.noise_model(&self.core().noise_model);
+        self.configure_noise(noise_model);
+    }
+
+    // Place the pair source; prepare-and-measure protocols ignore it
+    fn configure_source(&mut self, position: SourcePosition) {
+        self.core_mut().source_position = position;
+    }
+
+    // Set decoy intensities; single-photon protocols ignore them
+    fn configure_decoy(&mut self, config: DecoyConfig) {
+        self.core_mut().decoy_config = config;
+    }
+
+    // Fix the seed of the next run; unseeded runs draw a fresh one
+    fn set_seed(&mut self, seed: u64) {
+        self.core_mut().seed = Some(seed);
+    }
+
+    // Get current state
+    fn get_state(&self) -> SimulationState {
+        self.core().state.clone()
+    }
+
+    // Current state with the per-position arrays cut to `limit` positions from `offset`
+    fn get_state_window(&mut self, offset: usize, limit: usize) -> SimulationState {
+        self.core_mut().state_window(offset, limit)
+    }
+
+    // Counts, QBER and key length of the current run
+    fn get_summary(&self) -> StateSummary {
+        self.core().state.summary()
+    }
+
+    // Receive event batches from every following step
+    fn subscribe(&mut self) -> mpsc::Receiver<EventBatch> {
+        self.core_mut().events.subscribe()
+    }
+}
+
+fn record_key_rate<P: QkdProtocol + ?Sized>(protocol: &mut P) {
+    let report = protocol.key_rate_bound().map(|bound| key_rate_report(bound, &protocol.core().state));
+    protocol.core_mut().state.key_rate = report;
+}
+
+/// State and channel configuration owned by a protocol simulator.
+pub struct ProtocolCore {
+    pub state: SimulationState,
+    pub hacker_config: HackerConfig,
+    pub noise_model: NoiseModel,
+    pub seed: Option<u64>, // seed of the next run only, see `next_seed`
+    pub qber_threshold: Option<f64>,
+    pub events: EventSink,
+    pub bases: &'static [Basis], // bases Alice, Bob and Eve choose from
+    pub source_position: SourcePosition, // entanglement-based protocols only
+    pub decoy_config: DecoyConfig,       // weak-coherent-pulse protocols only
+    session_prefix: &'static str,
+}
+
+impl ProtocolCore {
+    pub fn new(session_prefix: &'static str) -> Self {
+        Self::with_bases(session_prefix, &Basis::BB84)
+    }
+
+    pub fn with_bases(session_prefix: &'static str, bases: &'static [Basis]) -> Self {
+        Self {
+            state: fresh_state(session_prefix),
+            hacker_config: HackerConfig::default(),
+            noise_model: NoiseModel::default(),
+            seed: None,
+            qber_threshold: None,
+            events: EventSink::default(),
+            bases,
+            source_position: SourcePosition::default(),
+            decoy_config: DecoyConfig::default(),
+            session_prefix,
+        }
+    }
+
+    pub fn generate_alice_bits(&mut self, count: usize) -> &PhotonColumns {
+        let bases = self.bases;
+        self.prepare_states(count, |rng| {
+            let value = rng.bit();
+            (rng.basis(bases), value)
+        })
+    }
+
+    /// Prepare `count` photons, drawing each one's `(basis, value)` state from
+    /// its own preparation stream with `choose_state`.
+    pub fn prepare_states<F>(&mut self, count: usize, choose_state: F) -> &PhotonColumns
+    where
+        F: Fn(&mut PhotonRng) -> (Basis, u8) + Sync,
+    {
+        let now = now_millis();
+        let drift = self.noise_model.polarization_drift;
+        let seed = self.next_seed();
+
+        let prepare = |i: usize| {
+            let mut rng = PhotonRng::new(seed, Stream::Preparation, i);
+            let (basis, value) = choose_state(&mut rng);
+            let photon = Photon::prepared(basis, value);
+
+            // Apply polarization drift based on time; rotating a circular
+            // state leaves it unchanged
+            match basis {
+                Basis::Circular => photon,
+                _ => Photon {
+                    polarization: wrap_degrees(photon.polarization + drift * (i as f64)),
+                    ..photon
+                },
+            }
+        };
+
+        self.state.alice_bits = collect_photons(Party::Alice, now, count, prepare);
+        self.state.start_time = now;
+        self.state.seed = seed;
+        self.set_phase(Phase::Transmission);
+        &self.state.alice_bits
+    }
+
+    // Seed for a new run: the one set with `set_seed`, which is used up, or a
+    // fresh one, so a seed one client passed never pins another's runs
+    pub fn next_seed(&mut self) -> u64 {
+        self.seed.take().unwrap_or_else(rand::random)
+    }
+
+    pub fn measure_bits(&mut self, hacker_present: bool) -> &PhotonColumns {
+        self.measure_announced(hacker_present, |_, alice_bit| Some(Announcement::of(alice_bit.basis)))
+    }
+
+    /// `measure_bits` for protocols that reveal something other than Alice's
+    /// basis during sifting. `announce` gives what becomes public about the
+    /// photon at a position, which is all Eve can read stored photons with.
+    pub fn measure_announced<F>(&mut self, hacker_present: bool, announce: F) -> &PhotonColumns
+    where
+        F: Fn(usize, &Photon) -> Option<Announcement> + Sync,
+    {
+        let hacker_config = self.hacker_config.clone();
+        let noise_model = self.noise_model.clone();
+        let bases = self.bases;
+        self.measure_with(hacker_present, |index, alice_bit, rng| {
+            let announced = announce(index, alice_bit);
+            measure_photon(alice_bit, announced, hacker_present, &hacker_config, &noise_model, bases, rng)
+        })
+    }
+
+    /// Run `measure` on every photon Alice sent, each with its own channel
+    /// stream, and move on to sifting. `measure` returns Bob's bit and Eve's
+    /// reading if she intercepted it.
+    pub fn measure_with<F>(&mut self, hacker_present: bool, measure_photon: F) -> &PhotonColumns
+    where
+        F: Fn(usize, &Photon, &mut PhotonRng) -> (Photon, Option<Photon>) + Sync,
+    {
+        let seed = self.state.seed;
+        let alice_bits = &self.state.alice_bits;
+        let measure = |index: usize| {
+            let mut rng = PhotonRng::new(seed, Stream::Channel, index);
+            measure_photon(index, &alice_bits.get(index), &mut rng)
+        };
+
+        // Stream progress in batches while someone listens, otherwise a chunk
+        // at a time. Use parallel processing for large counts
+        let count = alice_bits.len();
+        let parallel = count > PARALLEL_THRESHOLD;
+        let batch_size = if self.events.is_active() { EVENT_BATCH_SIZE } else { UNPACKED_CHUNK };
+        let mut bob_bits = PhotonColumns::dense(Party::Bob, self.state.start_time);
+        let mut intercepted_bits = PhotonColumns::sparse(Party::Eve, self.state.start_time);
+
+        for offset in (0..count).step_by(batch_size) {
+            let batch = offset..(offset + batch_size).min(count);
+            let results: Vec<(Photon, Option<Photon>)> = if parallel {
+                batch.into_par_iter().map(measure).collect()
+            } else {
+                batch.map(measure).collect()
+            };
+
+            if self.events.is_active() {
+                self.events.publish(transmission_events(offset, alice_bits, &results));
+            }
+            for (i, (bob_bit, intercepted)) in results.into_iter().enumerate() {
+                bob_bits.push(bob_bit);
+                if let Some(hacker_bit) = intercepted {
+                    intercepted_bits.push_at(offset + i, hacker_bit);
+                }
+            }
+        }
+
+        self.state.detection_stats = Some(DetectionStats::from_bits(&bob_bits));
+        self.state.bob_bits = bob_bits;
+        self.state.intercepted_bits = intercepted_bits;
+        self.state.is_hacker_present = hacker_present;
+        self.set_phase(Phase::Sifting);
+        &self.state.bob_bits
+    }
+
+    /// BB84-style sifting: keep Alice's bit wherever Bob used the same basis.
+    pub fn sift_matching_bases(&mut self) -> &BitVec {
+        let alice_bits = &self.state.alice_bits;
+        let bob_bits = &self.state.bob_bits;
+        let sift = |i: usize| {
+            let detected = alice_bits.is_detected(i) && bob_bits.is_detected(i);
+            (detected && alice_bits.basis(i) == bob_bits.basis(i)).then_some((alice_bits.value(i), bob_bits.value(i)))
+        };
+
+        // Use parallel processing for large counts
+        let count = self.sifting_positions();
+        let decisions: Vec<Option<(u8, u8)>> = if count > PARALLEL_THRESHOLD {
+            (0..count).into_par_iter().map(sift).collect()
+        } else {
+            (0..count).map(sift).collect()
+        };
+
+        self.state.expected_attack_qber = if self.state.is_hacker_present {
+            self.hacker_config.attack().expected_qber(self.bases)
+        } else {
+            None
+        };
+        self.assess_eavesdropper(&decisions);
+        self.reconcile(decisions)
+    }
+
+    // Positions both Alice and Bob hold a record for. Bob has none before
+    // measuring, and a regenerated run may be longer than his last one
+    pub fn sifting_positions(&self) -> usize {
+        self.state.alice_bits.len().min(self.state.bob_bits.len())
+    }
+
+    /// Compare Eve's records with the sifted key: her attack report, the
+    /// mutual information between the three parties and her guess of the key.
+    pub fn assess_eavesdropper(&mut self, decisions: &[Option<(u8, u8)>]) {
+        self.assess_eavesdropper_with(decisions, |_, bit| bit.value);
+    }
+
+    /// `assess_eavesdropper` for protocols whose key bit is not the measured
+    /// value; `key_bit` turns Eve's record at a position into her guess.
+    pub fn assess_eavesdropper_with<F>(&mut self, decisions: &[Option<(u8, u8)>], key_bit: F)
+    where
+        F: Fn(usize, &Photon) -> u8,
+    {
+        let view = eve_view(decisions.len(), &self.state.intercepted_bits, key_bit);
+        self.state.attack = self.state.is_hacker_present.then(|| attack_report(decisions, &view));
+        self.state.information = Some(information_report(decisions, &view));
+        self.state.eve_key = eve_guess(decisions, &view, self.state.seed);
+    }
+
+    /// Take the per-position sifting decision, `Some((alice, bob))` for kept
+    /// key bits, announce it to subscribers and move on to error checking.
+    pub fn reconcile(&mut self, decisions: Vec<Option<(u8, u8)>>) -> &BitVec {
+        if self.events.is_active() {
+            for (batch_index, batch) in decisions.chunks(EVENT_BATCH_SIZE).enumerate() {
+                let offset = batch_index * EVENT_BATCH_SIZE;
+                let events = batch
+                    .iter()
+                    .enumerate()
+                    .map(|(i, decision)| SimulationEvent::BasisReconciled {
+                        index: offset + i,
+                        alice_basis: self.state.alice_bits.basis(offset + i),
+                        bob_basis: self.state.bob_bits.basis(offset + i),
+                        kept: decision.is_some(),
+                    })
+                    .collect();
+                self.events.publish(events);
+            }
+        }
+
+        let positions = decisions.len();
+        let sifted: Vec<(u8, u8)> = decisions.into_iter().flatten().collect();
+        let (alice_bits, bob_bits) = (&self.state.alice_bits, &self.state.bob_bits);
+        self.state.sifting = Some(SiftingStats {
+            positions,
+            detected: (0..positions).filter(|&i| alice_bits.is_detected(i) && bob_bits.is_detected(i)).count(),
+            sifted: sifted.len(),
+            sifted_fraction: if positions == 0 { 0.0 } else { sifted.len() as f64 / positions as f64 },
+        });
+        let errors = sifted.iter().filter(|(alice, bob)| alice != bob).count();
+        self.state.error_rate = if sifted.is_empty() {
+            0.0
+        } else {
+            (errors as f64 / sifted.len() as f64) * 100.0
+        };
+
+        self.state.shared_key = sifted.iter().map(|&(alice, _)| alice).collect();
+        self.state.bob_key = sifted.iter().map(|&(_, bob)| bob).collect();
+        self.set_phase(Phase::ErrorCheck);
+        &self.state.shared_key
+    }
+
+    pub fn estimate_parameters(&mut self, sample_fraction: f64) -> ParameterEstimationReport {
+        let mut rng = PhotonRng::new(self.state.seed, Stream::ParameterEstimation, 0);
+        let outcome = estimate_qber(
+            &self.state.shared_key,
+            &self.state.bob_key,
+            sample_fraction,
+            self.state.error_rate / 100.0,
+            &mut rng,
+        );
+
+        // Eve's guess loses the same positions
+        if self.state.eve_key.len() == outcome.sampled.len() {
+            self.state.eve_key = self
+                .state
+                .eve_key
+                .iter()
+                .zip(&outcome.sampled)
+                .filter(|(_, &sampled)| !sampled)
+                .map(|(bit, _)| bit)
+                .collect();
+        }
+        self.state.shared_key = outcome.alice;
+        self.state.bob_key = outcome.bob;
+        self.state.parameter_estimation = Some(outcome.report.clone());
+        outcome.report
+    }
+
+    pub fn correct_errors(&mut self) -> ErrorCorrectionReport {
+        let alice = self.state.shared_key.unpack();
+        let bob = self.state.bob_key.unpack();
+        let qber = self.estimated_qber();
+        let mut rng = PhotonRng::new(self.state.seed, Stream::ErrorCorrection, 0);
+        let outcome = run_cascade(&alice, &bob, qber, &mut rng);
+
+        self.state.alice_corrected_key = self.state.shared_key.clone();
+        self.state.bob_corrected_key = outcome.corrected.iter().copied().collect();
+        self.state.error_correction = Some(outcome.report.clone());
+        self.set_phase(Phase::ErrorCorrection);
+        outcome.report
+    }
+
+    /// Hash the reconciled keys down to the finite-key length `bound` proves
+    /// secure, never more than `key_rate.finite_key` reports. Without a bound
+    /// or a parameter estimate, size it with `privacy::secure_length` at the
+    /// QBER's upper confidence limit instead.
+    pub fn amplify_privacy(&mut self, bound: Option<KeyRateBound>, epsilon: f64) -> PrivacyAmplificationReport {
+        let input_length = self.state.alice_corrected_key.len();
+        let output_length = bound
+            .and_then(|bound| finite_key_length(bound, &self.state, epsilon))
+            .unwrap_or_else(|| privacy::secure_length(input_length, self.qber_upper_bound(), self.leaked_bits(), epsilon));
+        self.amplify_to(output_length, epsilon)
+    }
+
+    /// Hash the reconciled keys down to `output_length` bits, for protocols
+    /// that size the secret key with their own bound.
+    pub fn amplify_to(&mut self, output_length: usize, epsilon: f64) -> PrivacyAmplificationReport {
+        let leaked_bits = self.leaked_bits();
+        let hash_seed = PhotonRng::new(self.state.seed, Stream::PrivacyAmplification, 0).next_u64();
+        let (secret, report) = privacy::amplify(
+            &self.state.alice_corrected_key,
+            &self.state.bob_corrected_key,
+            output_length,
+            leaked_bits,
+            epsilon,
+            hash_seed,
+        );
+
+        // Eve hashes her guess with the public Toeplitz matrix too
+        if let Some(information) = self.state.information.as_mut().filter(|_| !secret.is_empty()) {
+            let eve_secret = privacy::toeplitz_hash(&self.state.eve_key, secret.len(), hash_seed);
+            let errors = eve_secret.iter().zip(secret.iter()).filter(|(eve, alice)| eve != alice).count();
+            information.eve_final_key_error_rate = Some(errors as f64 / secret.len() as f64);
+        }
+        self.state.secret_key = secret;
+        self.state.privacy_amplification = Some(report.clone());
+        self.set_phase(Phase::PrivacyAmplification);
+        report
+    }
+
+    // Parities error correction disclosed to Eve
+    pub fn leaked_bits(&self) -> usize {
+        self.state.error_correction.as_ref().map_or(0, |report| report.disclosed_parities)
+    }
+
+    // QBER as Alice and Bob know it: the sampled estimate, falling back to
+    // the full comparison when parameter estimation was skipped
+    pub fn estimated_qber(&self) -> f64 {
+        match &self.state.parameter_estimation {
+            Some(estimate) => estimate.estimated_qber,
+            None => self.state.error_rate / 100.0,
+        }
+    }
+
+    // Upper confidence bound on the QBER, the safe value for security decisions
+    pub fn qber_upper_bound(&self) -> f64 {
+        match &self.state.parameter_estimation {
+            Some(estimate) => estimate.confidence_interval[1],
+            None => self.state.error_rate / 100.0,
+        }
+    }
+
+    pub fn complete(&mut self) -> SimulationState {
+        self.state.end_time = now_millis();
+        self.set_phase(Phase::Complete);
+        self.events.publish(vec![SimulationEvent::KeyFinalized {
+            key_length: self.state.secret_key.len(),
+            error_rate: self.state.error_rate,
+        }]);
+        self.state.clone()
+    }
+
+    // Give up on the run: no secret key is handed out
+    pub fn abort(&mut self, reason: AbortReason) -> SimulationState {
+        self.state.secret_key.clear();
+        self.state.abort_reason = Some(reason);
+        self.state.end_time = now_millis();
+        self.set_phase(Phase::Aborted);
+        self.state.clone()
+    }
+
+    /// Copy of the state whose per-position arrays only cover `limit`
+    /// positions from `offset`. The arrays are set aside while the rest of
+    /// the state is cloned, so the full run is never copied.
+    pub fn state_window(&mut self, offset: usize, limit: usize) -> SimulationState {
+        let alice_bits = std::mem::take(&mut self.state.alice_bits);
+        let bob_bits = std::mem::take(&mut self.state.bob_bits);
+        let intercepted_bits = std::mem::take(&mut self.state.intercepted_bits);
+        let announced_pairs = std::mem::take(&mut self.state.announced_pairs);
+        let analyzer_angles = std::mem::take(&mut self.state.analyzer_angles);
+        let pulses = std::mem::take(&mut self.state.pulses);
+
+        let range = offset..offset.saturating_add(limit);
+        let mut state = self.state.clone();
+        state.alice_bits = alice_bits.window(range.clone());
+        state.bob_bits = bob_bits.window(range.clone());
+        state.intercepted_bits = intercepted_bits.window(range.clone());
+        state.announced_pairs = window_of(&announced_pairs, &range);
+        state.analyzer_angles = window_of(&analyzer_angles, &range);
+        state.pulses = window_of(&pulses, &range);
+        state.window = Some(StateWindow {
+            offset,
+            limit: limit.min(alice_bits.len().saturating_sub(offset)),
+            positions: alice_bits.len(),
+        });
+
+        self.state.alice_bits = alice_bits;
+        self.state.bob_bits = bob_bits;
+        self.state.intercepted_bits = intercepted_bits;
+        self.state.announced_pairs = announced_pairs;
+        self.state.analyzer_angles = analyzer_angles;
+        self.state.pulses = pulses;
+        state
+    }
+
+    // Start a new run; the session id stays so session lookups remain valid
+    pub fn reset(&mut self) {
+        let session_id = std::mem::take(&mut self.state.session_id);
+        self.state = fresh_state(self.session_prefix);
+        self.seed = None;
+        self.state.session_id = session_id;
+        self.set_phase(Phase::Preparation);
+    }
+
+    pub fn set_phase(&mut self, phase: Phase) {
+        self.state.phase = phase;
+        self.events.publish(vec![SimulationEvent::PhaseChanged { phase }]);
+    }
+}
+
+fn fresh_state(session_prefix: &str) -> SimulationState {
+    SimulationState {
+        alice_bits: PhotonColumns::default(),
+        bob_bits: PhotonColumns::default(),
+        shared_key: BitVec::new(),
+        intercepted_bits: PhotonColumns::default(),
+        error_rate: 0.0,
+        is_hacker_present: false,
+        phase: Phase::Preparation,
+        session_id: format!("{}-{}", session_prefix, Uuid::new_v4()),
+        start_time: now_millis(),
+        end_time: 0,
+        announced_pairs: Vec::new(),
+        seed: 0,
+        bob_key: BitVec::new(),
+        parameter_estimation: None,
+        alice_corrected_key: BitVec::new(),
+        bob_corrected_key: BitVec::new(),
+        error_correction: None,
+        secret_key: BitVec::new(),
+        privacy_amplification: None,
+        abort_reason: None,
+        detection_stats: None,
+        sifting: None,
+        expected_attack_qber: None,
+        attack: None,
+        information: None,
+        eve_key: BitVec::new(),
+        key_rate: None,
+        analyzer_angles: Vec::new(),
+        chsh: None,
+        pulses: Vec::new(),
+        decoy: None,
+        pns: None,
+        window: None,
+    }
+}
+
+// The items of a per-position array that fall in `range`
+fn window_of<T: Clone>(items: &[T], range: &Range<usize>) -> Vec<T> {
+    items[range.start.min(items.len())..range.end.min(items.len())].to_vec()
+}
+
+fn transmission_events(
+    offset: usize,
+    alice_bits: &PhotonColumns,
+    results: &[(Photon, Option<Photon>)],
+) -> Vec<SimulationEvent> {
+    let mut events = Vec::with_capacity(results.len() * 2);
+    for (i, (bob_bit, intercepted)) in results.iter().enumerate() {
+        let index = offset + i;
+        let alice_bit = alice_bits.get(index);
+        events.push(SimulationEvent::PhotonEmitted {
+            index,
+            basis: alice_bit.basis,
+            polarization: alice_bit.polarization,
+        });
+        if let Some(hacker_bit) = intercepted {
+            events.push(SimulationEvent::EveIntercepted {
+                index,
+                basis: hacker_bit.basis,
+                value: hacker_bit.value,
+            });
+        }
+        events.push(SimulationEvent::BobDetected {
+            index,
+            basis: bob_bit.basis,
+            value: bob_bit.value,
+            detection: bob_bit.detection.unwrap_or(Detection::Click),
+        });
+    }
+    events
+}
+
+/// Pack the photons `photon_at` produces for positions `0..count` into
+/// `party`'s columns, a chunk at a time.
+pub fn collect_photons<F>(party: Party, epoch: u64, count: usize, photon_at: F) -> PhotonColumns
+where
+    F: Fn(usize) -> Photon + Sync,
+{
+    let mut columns = PhotonColumns::dense(party, epoch);
+    for start in (0..count).step_by(UNPACKED_CHUNK) {
+        let chunk = start..(start + UNPACKED_CHUNK).min(count);
+        // Use parallel processing for large counts
+        let photons: Vec<Photon> = if count > PARALLEL_THRESHOLD {
+            chunk.into_par_iter().map(&photon_at).collect()
+        } else {
+            chunk.map(&photon_at).collect()
+        };
+        for photon in photons {
+            columns.push(photon);
+        }
+    }
+    columns
+}
+
+// Send a single photon through the (possibly tapped) channel to Bob;
+// `announced` is what sifting will reveal about it
+pub fn measure_photon(
+    alice_bit: &Photon,
+    announced: Option<Announcement>,
+    hacker_present: bool,
+    hacker_config: &HackerConfig,
+    noise_model: &NoiseModel,
+    bases: &[Basis],
+    rng: &mut PhotonRng,
+) -> (Photon, Option<Photon>) {
+    let photon = InFlight {
+        polarization: Polarization::from_degrees(alice_bit.polarization),
+        photons: 1,
+    };
+    let attack = hacker_present.then(|| hacker_config.attack());
+    let (arriving, intercepted) = transmit(photon, announced, attack, noise_model.loss_probability, bases, rng);
+
+    // Bob's random basis choice
+    let bob_basis = rng.basis(bases);
+    let analyzer = Polarization::of(bob_basis, 0).rotated(noise_model.analyzer_misalignment);
+    let (detection, bob_value) = detect(arriving, analyzer, noise_model, rng);
+
+    let bob_bit = Photon {
+        detection: Some(detection),
+        ..Photon::prepared(bob_basis, bob_value)
+    };
+
+    (bob_bit, intercepted)
+}
+
+/// What Bob's two detectors report when `signal` names the one the photon
+/// reached (if any); either detector may also fire on a dark count.
+pub fn register_clicks(signal: Option<u8>, noise_model: &NoiseModel, rng: &mut PhotonRng) -> (Detection, u8) {
+    register_hits([signal == Some(0), signal == Some(1)], noise_model, rng)
+}
+
+/// Like `register_clicks` for pulses that may hit both detectors at once.
+pub fn register_hits(hits: [bool; 2], noise_model: &NoiseModel, rng: &mut PhotonRng) -> (Detection, u8) {
+    let fired = [
+        hits[0] || rng.chance(noise_model.dark_count_rate),
+        hits[1] || rng.chance(noise_model.dark_count_rate),
+    ];
+    match fired {
+        [true, false] => (Detection::Click, 0),
+        [false, true] => (Detection::Click, 1),
+        // Both fired: squash to a random bit so the event stays usable
+        [true, true] => (Detection::DoubleClick, rng.bit()),
+        [false, false] => (Detection::NoClick, 0),
+    }
+}
+
+// Circular states have no linear angle; they are reported outside 0–179°
+pub const RIGHT_CIRCULAR: f64 = 180.0;
+pub const LEFT_CIRCULAR: f64 = 270.0;
+
+// Map bit value and basis to polarization
+pub fn polarization_for(basis: &Basis, value: u8) -> f64 {
+    match (basis, value) {
+        (Basis::Rectilinear, 0) => 0.0,
+        (Basis::Rectilinear, _) => 90.0,
+        (Basis::Diagonal, 0) => 45.0,
+        (Basis::Diagonal, _) => 135.0,
+        (Basis::Circular, 0) => RIGHT_CIRCULAR,
+        (Basis::Circular, _) => LEFT_CIRCULAR,
+    }
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
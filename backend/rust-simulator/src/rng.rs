@@ -0,0 +1,85 @@
+use crate::models::Basis;
+use rand::{RngCore, Error};
+
+/// Independent random streams drawn from one simulation seed.
+#[derive(Debug, Clone, Copy)]
+pub enum Stream {
+    Preparation = 1,          // Alice's bit and basis choices
+    Channel = 2,              // loss, noise, Eve and Bob's measurement
+    Sifting = 3,              // public announcements during sifting
+    ErrorCorrection = 4,      // Cascade block shuffles
+    PrivacyAmplification = 5, // Toeplitz hash seed and matrix
+    ParameterEstimation = 6,  // choice of publicly compared positions
+    Source = 7,               // entangled source and Alice's arm of the link
+    Intensity = 8,            // decoy level and photon number of each pulse
+    Eavesdropper = 9,         // Eve's guesses where she holds no record
+    Sweep = 10,               // seeds of the runs in a parameter sweep
+}
+
+/// Counter-based random source.
+///
+/// Every `(seed, stream, index)` triple gets its own SplitMix64 sequence, so
+/// the draws for photon `i` are the same whichever thread handles it and
+/// whether the parallel or sequential path runs.
+#[derive(Debug, Clone)]
+pub struct PhotonRng {
+    state: u64,
+}
+
+impl PhotonRng {
+    pub fn new(seed: u64, stream: Stream, index: usize) -> Self {
+        let stream_key = mix(seed ^ (stream as u64).wrapping_mul(0xD1B5_4A32_D192_ED03));
+        Self {
+            state: mix(stream_key ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+        }
+    }
+
+    // Uniform in [0, 1) with 53 bits of precision
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    pub fn bit(&mut self) -> u8 {
+        if self.next_f64() < 0.5 { 0 } else { 1 }
+    }
+
+    // Uniform choice among `bases`
+    pub fn basis(&mut self, bases: &[Basis]) -> Basis {
+        let index = (self.next_f64() * bases.len() as f64) as usize;
+        bases[index.min(bases.len() - 1)]
+    }
+}
+
+impl RngCore for PhotonRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.state)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// SplitMix64 finaliser
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
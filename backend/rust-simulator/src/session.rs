@@ -0,0 +1,165 @@
+use crate::protocol::QkdProtocol;
+use crate::simulator::BB84Simulator;
+use crate::sarg04::SARG04Simulator;
+use crate::b92::B92Simulator;
+use crate::six_state::SixStateSimulator;
+use crate::e91::E91Simulator;
+use crate::bbm92::BBM92Simulator;
+use crate::decoy::DecoyBB84Simulator;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub type SharedProtocol = Arc<Mutex<dyn QkdProtocol>>;
+
+/// Protocols that can be chosen when a session is created.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtocolKind {
+    Bb84,
+    Sarg04,
+    B92,
+    #[serde(rename = "six-state")]
+    SixState,
+    E91,
+    Bbm92,
+    #[serde(rename = "decoy-bb84")]
+    DecoyBb84,
+}
+
+impl ProtocolKind {
+    pub fn create(self) -> SharedProtocol {
+        match self {
+            ProtocolKind::Bb84 => Arc::new(Mutex::new(BB84Simulator::new())),
+            ProtocolKind::Sarg04 => Arc::new(Mutex::new(SARG04Simulator::new())),
+            ProtocolKind::B92 => Arc::new(Mutex::new(B92Simulator::new())),
+            ProtocolKind::SixState => Arc::new(Mutex::new(SixStateSimulator::new())),
+            ProtocolKind::E91 => Arc::new(Mutex::new(E91Simulator::new())),
+            ProtocolKind::Bbm92 => Arc::new(Mutex::new(BBM92Simulator::new())),
+            ProtocolKind::DecoyBb84 => Arc::new(Mutex::new(DecoyBB84Simulator::new())),
+        }
+    }
+
+    // A simulator owned by the caller, for batch runs outside any session
+    pub fn simulator(self) -> Box<dyn QkdProtocol> {
+        match self {
+            ProtocolKind::Bb84 => Box::new(BB84Simulator::new()),
+            ProtocolKind::Sarg04 => Box::new(SARG04Simulator::new()),
+            ProtocolKind::B92 => Box::new(B92Simulator::new()),
+            ProtocolKind::SixState => Box::new(SixStateSimulator::new()),
+            ProtocolKind::E91 => Box::new(E91Simulator::new()),
+            ProtocolKind::Bbm92 => Box::new(BBM92Simulator::new()),
+            ProtocolKind::DecoyBb84 => Box::new(DecoyBB84Simulator::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub max_sessions: usize,
+    pub idle_ttl: Duration,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: 100,
+            idle_ttl: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    LimitReached(usize),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::LimitReached(max) => write!(f, "session limit of {} reached", max),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+struct Session {
+    simulator: SharedProtocol,
+    last_access: Instant,
+}
+
+/// Simulators keyed by their `SimulationState::session_id`.
+pub struct SessionRegistry {
+    sessions: HashMap<String, Session>,
+    config: SessionConfig,
+}
+
+impl SessionRegistry {
+    pub fn new(config: SessionConfig) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            config,
+        }
+    }
+
+    /// Register a new simulator, making room by evicting idle sessions first.
+    pub async fn create(&mut self, protocol: ProtocolKind, seed: Option<u64>) -> Result<(String, SharedProtocol), SessionError> {
+        self.evict_idle();
+        if self.sessions.len() >= self.config.max_sessions {
+            return Err(SessionError::LimitReached(self.config.max_sessions));
+        }
+
+        let simulator = protocol.create();
+        let session_id = {
+            let mut sim = simulator.lock().await;
+            if let Some(seed) = seed {
+                sim.set_seed(seed);
+            }
+            sim.get_state().session_id
+        };
+
+        self.sessions.insert(
+            session_id.clone(),
+            Session {
+                simulator: simulator.clone(),
+                last_access: Instant::now(),
+            },
+        );
+        Ok((session_id, simulator))
+    }
+
+    // Look up a session and mark it as recently used
+    pub fn get(&mut self, session_id: &str) -> Option<SharedProtocol> {
+        let session = self.sessions.get_mut(session_id)?;
+        session.last_access = Instant::now();
+        Some(session.simulator.clone())
+    }
+
+    pub fn remove(&mut self, session_id: &str) -> bool {
+        self.sessions.remove(session_id).is_some()
+    }
+
+    // Drop every session that has not been touched within the idle TTL
+    pub fn evict_idle(&mut self) -> usize {
+        let before = self.sessions.len();
+        let idle_ttl = self.config.idle_ttl;
+        self.sessions.retain(|_, session| session.last_access.elapsed() < idle_ttl);
+        before - self.sessions.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+}
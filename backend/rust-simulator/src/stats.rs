@@ -0,0 +1,46 @@
+// Binary Shannon entropy h(p) in bits
+pub fn binary_entropy(p: f64) -> f64 {
+    if p <= 0.0 || p >= 1.0 {
+        0.0
+    } else {
+        -p * p.log2() - (1.0 - p) * (1.0 - p).log2()
+    }
+}
+
+/// Plug-in estimate of I(X:Y) in bits from a table of joint counts.
+pub fn mutual_information<const N: usize, const M: usize>(counts: &[[usize; M]; N]) -> f64 {
+    let total: usize = counts.iter().flatten().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let n = total as f64;
+    let rows: Vec<f64> = counts.iter().map(|row| row.iter().sum::<usize>() as f64 / n).collect();
+    let columns: Vec<f64> = (0..M).map(|y| counts.iter().map(|row| row[y]).sum::<usize>() as f64 / n).collect();
+
+    let mut information = 0.0;
+    for (x, row) in counts.iter().enumerate() {
+        for (y, &count) in row.iter().enumerate() {
+            if count > 0 {
+                let joint = count as f64 / n;
+                information += joint * (joint / (rows[x] * columns[y])).log2();
+            }
+        }
+    }
+    information.max(0.0)
+}
+
+// Two-sided normal quantile for a 95% confidence level
+pub const Z_95: f64 = 1.959_964;
+
+/// Wilson score interval for a binomial proportion of `successes` in `trials`.
+pub fn wilson_interval(successes: usize, trials: usize, z: f64) -> (f64, f64) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+    let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half_width = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+    ((centre - half_width).max(0.0), (centre + half_width).min(1.0))
+}
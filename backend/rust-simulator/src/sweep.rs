@@ -0,0 +1,208 @@
+use crate::models::{FiberChannel, HackerConfig, NoiseModel};
+use crate::rng::{PhotonRng, Stream};
+use crate::session::ProtocolKind;
+use rand::RngCore;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Upper bound on photons simulated by one sweep, across all points and repetitions
+pub const MAX_SWEEP_PHOTONS: usize = 200_000_000;
+
+// Upper bound on runs in one sweep, however few photons each sends
+pub const MAX_SWEEP_RUNS: usize = 100_000;
+
+// The setting a sweep steps through
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepParameter {
+    DistanceKm,       // fiber length, on top of `channel` or the default fiber
+    LossProbability,  // `noise_model.loss_probability`, over `channel` when set
+    DarkCountRate,    // `noise_model.dark_count_rate`, over `channel` when set
+    InterceptionRate, // Eve's interception rate (split ratio for beam splitting)
+}
+
+/// One sweep: `steps` evenly spaced values from `start` to `end`, each run
+/// `repetitions` times from generation through `complete`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepRequest {
+    pub protocol: ProtocolKind,
+    pub parameter: SweepParameter,
+    pub start: f64,
+    pub end: f64,
+    pub steps: usize,
+    #[serde(default = "default_repetitions")]
+    pub repetitions: usize,
+    #[serde(default = "default_bit_count")]
+    pub bit_count: usize,
+    #[serde(default)]
+    pub noise_model: NoiseModel,
+    #[serde(default)]
+    pub channel: Option<FiberChannel>, // overrides the noise model's loss and detectors
+    #[serde(default)]
+    pub hacker_config: Option<HackerConfig>, // Eve is present when set
+    #[serde(default)]
+    pub seed: Option<u64>, // fixes every run's seed, making the sweep reproducible
+}
+
+fn default_repetitions() -> usize {
+    10
+}
+
+fn default_bit_count() -> usize {
+    10_000
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Summary {
+    pub mean: f64,
+    pub std_dev: f64, // sample standard deviation, 0 for a single repetition
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepPoint {
+    pub value: f64,
+    pub error_rate: Summary, // percent, like `SimulationState::error_rate`
+    pub sifted_length: Summary,
+    pub key_length: Summary, // secret key bits, 0 for aborted runs
+    pub aborted: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepResult {
+    pub parameter: SweepParameter,
+    pub repetitions: usize,
+    pub points: Vec<SweepPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SweepError {
+    NoPoints,
+    TooLarge(usize),
+    TooManyRuns(usize),
+}
+
+impl fmt::Display for SweepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SweepError::NoPoints => write!(f, "a sweep needs at least one step, one repetition and one photon"),
+            SweepError::TooLarge(max) => write!(f, "a sweep may simulate at most {} photons", max),
+            SweepError::TooManyRuns(max) => write!(f, "a sweep may run at most {} simulations", max),
+        }
+    }
+}
+
+impl std::error::Error for SweepError {}
+
+// What one run contributes to its point
+struct RunOutcome {
+    error_rate: f64,
+    sifted_length: f64,
+    key_length: f64,
+    aborted: bool,
+}
+
+/// Run every point of the sweep, all repetitions in parallel.
+pub fn run_sweep(request: &SweepRequest) -> Result<SweepResult, SweepError> {
+    if request.steps == 0 || request.repetitions == 0 || request.bit_count == 0 {
+        return Err(SweepError::NoPoints);
+    }
+    let runs = request
+        .steps
+        .checked_mul(request.repetitions)
+        .filter(|&runs| runs <= MAX_SWEEP_RUNS)
+        .ok_or(SweepError::TooManyRuns(MAX_SWEEP_RUNS))?;
+    let photons = runs.saturating_mul(request.bit_count);
+    if photons > MAX_SWEEP_PHOTONS {
+        return Err(SweepError::TooLarge(MAX_SWEEP_PHOTONS));
+    }
+
+    let base_seed = request.seed.unwrap_or_else(rand::random);
+    let values: Vec<f64> = (0..request.steps).map(|step| sweep_value(request, step)).collect();
+    let outcomes: Vec<RunOutcome> = (0..runs)
+        .into_par_iter()
+        .map(|run| {
+            let seed = PhotonRng::new(base_seed, Stream::Sweep, run).next_u64();
+            run_once(request, values[run / request.repetitions], seed)
+        })
+        .collect();
+
+    let points = values
+        .iter()
+        .zip(outcomes.chunks(request.repetitions))
+        .map(|(&value, runs)| SweepPoint {
+            value,
+            error_rate: summarize(runs.iter().map(|run| run.error_rate)),
+            sifted_length: summarize(runs.iter().map(|run| run.sifted_length)),
+            key_length: summarize(runs.iter().map(|run| run.key_length)),
+            aborted: runs.iter().filter(|run| run.aborted).count(),
+        })
+        .collect();
+
+    Ok(SweepResult {
+        parameter: request.parameter,
+        repetitions: request.repetitions,
+        points,
+    })
+}
+
+fn sweep_value(request: &SweepRequest, step: usize) -> f64 {
+    if request.steps == 1 {
+        request.start
+    } else {
+        request.start + (request.end - request.start) * step as f64 / (request.steps - 1) as f64
+    }
+}
+
+fn run_once(request: &SweepRequest, value: f64, seed: u64) -> RunOutcome {
+    let mut channel = request.channel.clone();
+    if request.parameter == SweepParameter::DistanceKm {
+        channel.get_or_insert_with(FiberChannel::default).length_km = value;
+    }
+
+    // The channel sets the loss and detectors first, so a swept loss or dark
+    // count rate replaces the channel's instead of being overwritten by it
+    let mut noise_model = match &channel {
+        Some(channel) => channel.noise_model(&request.noise_model),
+        None => request.noise_model.clone(),
+    };
+    let mut hacker_config = request.hacker_config.clone();
+    match request.parameter {
+        SweepParameter::DistanceKm => {}
+        SweepParameter::LossProbability => noise_model.loss_probability = value,
+        SweepParameter::DarkCountRate => noise_model.dark_count_rate = value,
+        SweepParameter::InterceptionRate => {
+            hacker_config = Some(hacker_config.unwrap_or_default().with_interception_rate(value))
+        }
+    }
+
+    let mut sim = request.protocol.simulator();
+    sim.set_seed(seed);
+    sim.configure_noise(noise_model);
+    if let Some(config) = hacker_config.clone() {
+        sim.configure_hacker(config);
+    }
+
+    sim.generate_alice_bits(request.bit_count);
+    sim.measure_bits(hacker_config.is_some());
+    sim.sift_key();
+    let state = sim.complete_simulation();
+    RunOutcome {
+        error_rate: state.error_rate,
+        sifted_length: state.sifting.map_or(0, |sifting| sifting.sifted) as f64,
+        key_length: state.secret_key.len() as f64,
+        aborted: state.abort_reason.is_some(),
+    }
+}
+
+fn summarize(values: impl Iterator<Item = f64>) -> Summary {
+    let values: Vec<f64> = values.collect();
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let std_dev = if values.len() > 1 {
+        (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+    } else {
+        0.0
+    };
+    Summary { mean, std_dev }
+}
@@ -0,0 +1,34 @@
+use qkd_simulator::attack::PhotonNumberSplitting;
+use qkd_simulator::{AbortReason, DecoyBB84Simulator, HackerConfig, Phase, QkdProtocol, SimulationState};
+
+// A seeded decoy-state run through `/complete`, under `attack` if given
+fn complete_decoy_run(attack: Option<HackerConfig>) -> SimulationState {
+    let mut sim = DecoyBB84Simulator::new();
+    sim.set_seed(3);
+    let hacker_present = attack.is_some();
+    if let Some(attack) = attack {
+        sim.configure_hacker(attack);
+    }
+    sim.generate_alice_bits(200_000);
+    sim.measure_bits(hacker_present);
+    sim.sift_key();
+    sim.complete_simulation()
+}
+
+#[test]
+fn an_undisturbed_decoy_run_keeps_a_key() {
+    let state = complete_decoy_run(None);
+
+    assert_eq!(state.phase, Phase::Complete);
+    assert!(!state.secret_key.is_empty());
+}
+
+#[test]
+fn a_decoy_run_under_photon_number_splitting_keeps_no_key() {
+    let attack = HackerConfig::PhotonNumberSplitting(PhotonNumberSplitting { interception_rate: 1.0 });
+    let state = complete_decoy_run(Some(attack));
+
+    assert_eq!(state.phase, Phase::Aborted);
+    assert!(matches!(state.abort_reason, Some(AbortReason::NoSecretKey { .. })));
+    assert!(state.secret_key.is_empty());
+}
@@ -0,0 +1,60 @@
+use qkd_simulator::attack::{Breidbart, PhaseCovariantCloning};
+use qkd_simulator::{AttackReport, B92Simulator, BB84Simulator, E91Simulator, HackerConfig, QkdProtocol, SARG04Simulator};
+
+const PHOTONS: usize = 200_000;
+
+// Eve's attack report on a seeded, noiseless-channel run under `attack`
+fn attack_report(mut sim: impl QkdProtocol, attack: HackerConfig) -> AttackReport {
+    sim.set_seed(11);
+    sim.configure_hacker(attack);
+    sim.generate_alice_bits(PHOTONS);
+    sim.measure_bits(true);
+    sim.sift_key();
+    sim.get_state().attack.expect("Eve was present")
+}
+
+fn cloning() -> HackerConfig {
+    HackerConfig::PhaseCovariantCloning(PhaseCovariantCloning { interception_rate: 1.0 })
+}
+
+#[test]
+fn cloning_reads_bb84_copies_in_the_announced_basis() {
+    let report = attack_report(BB84Simulator::new(), cloning());
+
+    // Fidelity (1 + 1/√2)/2 once the basis is known
+    assert!((report.guess_accuracy - 0.854).abs() < 0.01, "{}", report.guess_accuracy);
+}
+
+#[test]
+fn cloning_on_sarg04_only_learns_the_announced_pair() {
+    let report = attack_report(SARG04Simulator::new(), cloning());
+
+    // The best measurement between two states 45° apart, on a copy of
+    // Stokes length 1/√2, is right (1 + 1/2)/2 = 75% of the time
+    assert!((report.guess_accuracy - 0.75).abs() < 0.01, "{}", report.guess_accuracy);
+}
+
+#[test]
+fn cloning_on_b92_has_no_announcement_to_use() {
+    let report = attack_report(B92Simulator::new(), cloning());
+
+    // A random basis: right 85.4% in the state's basis, 50% in the other
+    assert!((report.guess_accuracy - 0.677).abs() < 0.01, "{}", report.guess_accuracy);
+}
+
+#[test]
+fn breidbart_learns_nothing_about_b92_states() {
+    let attack = HackerConfig::Breidbart(Breidbart { interception_rate: 1.0 });
+    let report = attack_report(B92Simulator::new(), attack);
+
+    // 22.5° is as near to 0° as to 45°
+    assert!((report.guess_accuracy - 0.5).abs() < 0.01, "{}", report.guess_accuracy);
+}
+
+#[test]
+fn cloning_on_e91_reads_copies_at_the_announced_analyzer_angle() {
+    let report = attack_report(E91Simulator::new(), cloning());
+
+    // Alice's analyzer angle plays the part of the announced basis
+    assert!((report.guess_accuracy - 0.854).abs() < 0.01, "{}", report.guess_accuracy);
+}
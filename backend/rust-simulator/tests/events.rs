@@ -0,0 +1,22 @@
+use qkd_simulator::events::SUBSCRIBER_CAPACITY;
+use qkd_simulator::{BB84Simulator, QkdProtocol};
+use tokio::sync::mpsc::error::TryRecvError;
+
+#[test]
+fn a_subscriber_that_falls_behind_is_dropped_instead_of_stalling_the_run() {
+    let mut sim = BB84Simulator::new();
+    let mut events = sim.subscribe();
+    sim.generate_alice_bits(200_000);
+    sim.measure_bits(false);
+
+    let mut received = 0;
+    let disconnected = loop {
+        match events.try_recv() {
+            Ok(_) => received += 1,
+            Err(err) => break err == TryRecvError::Disconnected,
+        }
+    };
+    assert_eq!(received, SUBSCRIBER_CAPACITY);
+    assert!(disconnected);
+    assert!(sim.get_state().detection_stats.is_some());
+}
@@ -0,0 +1,31 @@
+use qkd_simulator::{BB84Simulator, NoiseModel, Phase, QkdProtocol, SimulationState, SixStateSimulator};
+
+// A seeded run through `/complete` with a misaligned analyzer, sin²(13°) ≈ 5% QBER
+fn complete_noisy_run(mut sim: impl QkdProtocol) -> SimulationState {
+    sim.set_seed(5);
+    sim.configure_noise(NoiseModel { analyzer_misalignment: 13.0, ..NoiseModel::default() });
+    sim.generate_alice_bits(100_000);
+    sim.measure_bits(false);
+    sim.sift_key();
+    sim.complete_simulation()
+}
+
+fn assert_key_within_finite_key_lengths(state: &SimulationState) {
+    assert_eq!(state.phase, Phase::Complete);
+    let finite_key = &state.key_rate.as_ref().expect("key rate is reported").finite_key;
+    let shortest = finite_key.iter().map(|report| report.key_length).min().unwrap();
+
+    assert_eq!(finite_key.len(), 2);
+    assert!(shortest > 0);
+    assert_eq!(state.secret_key.len(), shortest);
+}
+
+#[test]
+fn bb84_secret_key_is_no_longer_than_the_finite_key_length() {
+    assert_key_within_finite_key_lengths(&complete_noisy_run(BB84Simulator::new()));
+}
+
+#[test]
+fn six_state_secret_key_is_no_longer_than_the_finite_key_length() {
+    assert_key_within_finite_key_lengths(&complete_noisy_run(SixStateSimulator::new()));
+}
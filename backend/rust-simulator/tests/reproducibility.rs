@@ -0,0 +1,43 @@
+use qkd_simulator::protocol::PARALLEL_THRESHOLD;
+use qkd_simulator::{BB84Simulator, Photon, QkdProtocol};
+
+// Alice's and Bob's photons and the sifted key of a seeded BB84 run with Eve
+fn run(seed: u64, count: usize) -> (Vec<Photon>, Vec<Photon>, String) {
+    let mut sim = BB84Simulator::new();
+    sim.set_seed(seed);
+    sim.generate_alice_bits(count);
+    sim.measure_bits(true);
+    sim.sift_key();
+    let state = sim.get_state();
+    (state.alice_bits.iter().collect(), state.bob_bits.iter().collect(), state.shared_key.to_string())
+}
+
+#[test]
+fn parallel_and_sequential_runs_draw_the_same_photons() {
+    let sequential = run(42, PARALLEL_THRESHOLD);
+    let parallel = run(42, 3 * PARALLEL_THRESHOLD);
+
+    assert_eq!(sequential.0[..], parallel.0[..PARALLEL_THRESHOLD]);
+    assert_eq!(sequential.1[..], parallel.1[..PARALLEL_THRESHOLD]);
+    assert!(parallel.2.starts_with(&sequential.2));
+}
+
+#[test]
+fn runs_do_not_depend_on_the_thread_count() {
+    let single_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    let parallel = run(7, 5 * PARALLEL_THRESHOLD);
+
+    assert_eq!(single_thread.install(|| run(7, 5 * PARALLEL_THRESHOLD)), parallel);
+}
+
+#[test]
+fn a_seed_only_fixes_the_run_it_starts() {
+    let mut sim = BB84Simulator::new();
+    sim.set_seed(7);
+    sim.generate_alice_bits(10);
+    assert_eq!(sim.get_state().seed, 7);
+
+    sim.reset();
+    sim.generate_alice_bits(10);
+    assert_ne!(sim.get_state().seed, 7);
+}
@@ -0,0 +1,35 @@
+use qkd_simulator::{
+    B92Simulator, BB84Simulator, BBM92Simulator, DecoyBB84Simulator, E91Simulator, QkdProtocol, SARG04Simulator,
+    SixStateSimulator,
+};
+
+fn protocols() -> Vec<Box<dyn QkdProtocol>> {
+    vec![
+        Box::new(BB84Simulator::new()),
+        Box::new(SARG04Simulator::new()),
+        Box::new(B92Simulator::new()),
+        Box::new(SixStateSimulator::new()),
+        Box::new(E91Simulator::new()),
+        Box::new(BBM92Simulator::new()),
+        Box::new(DecoyBB84Simulator::new()),
+    ]
+}
+
+#[test]
+fn sifting_before_measuring_keeps_nothing() {
+    for mut sim in protocols() {
+        sim.generate_alice_bits(2000);
+        assert!(sim.sift_key().is_empty(), "{}", sim.name());
+    }
+}
+
+#[test]
+fn sifting_a_regenerated_longer_run_only_covers_measured_positions() {
+    for mut sim in protocols() {
+        sim.generate_alice_bits(100);
+        sim.measure_bits(true);
+        sim.generate_alice_bits(2000);
+        sim.sift_key();
+        assert_eq!(sim.get_state().sifting.unwrap().positions, 100, "{}", sim.name());
+    }
+}
@@ -0,0 +1,33 @@
+use qkd_simulator::{run_sweep, SweepRequest, SweepResult};
+use serde_json::json;
+
+// A seeded BB84 sweep over a default fiber link
+fn sweep_over_channel(parameter: &str, start: f64, end: f64) -> SweepResult {
+    let request: SweepRequest = serde_json::from_value(json!({
+        "protocol": "bb84",
+        "parameter": parameter,
+        "start": start, "end": end, "steps": 3,
+        "repetitions": 2,
+        "bit_count": 10000,
+        "channel": {},
+        "seed": 1,
+    }))
+    .unwrap();
+    run_sweep(&request).unwrap()
+}
+
+#[test]
+fn loss_sweeps_replace_the_channel_loss() {
+    let result = sweep_over_channel("loss_probability", 0.0, 0.9);
+    let sifted: Vec<f64> = result.points.iter().map(|point| point.sifted_length.mean).collect();
+
+    assert!(sifted.windows(2).all(|pair| pair[0] > 1.5 * pair[1]), "{:?}", sifted);
+}
+
+#[test]
+fn dark_count_sweeps_replace_the_channel_dark_counts() {
+    let result = sweep_over_channel("dark_count_rate", 0.0, 0.2);
+    let error_rates: Vec<f64> = result.points.iter().map(|point| point.error_rate.mean).collect();
+
+    assert!(error_rates.windows(2).all(|pair| pair[0] + 2.0 < pair[1]), "{:?}", error_rates);
+}